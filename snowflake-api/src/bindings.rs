@@ -0,0 +1,560 @@
+use std::collections::HashMap;
+
+use arrow::array::{Array, AsArray};
+use arrow::datatypes::{
+    DataType, Date32Type, Date64Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+    Int8Type, Time32MillisecondType, Time32SecondType, Time64MicrosecondType, Time64NanosecondType,
+    TimeUnit, TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+
+use crate::requests::{BindParameter, BindParameterValue};
+use crate::SnowflakeApiError;
+
+/// Value bound to a statement placeholder (`?`, `:1` or `:name`).
+/// Variants follow [`SnowflakeType`](crate::responses::SnowflakeType), plus explicit `NULL`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindValue {
+    Null,
+    Fixed(i64),
+    Real(f64),
+    Text(String),
+    Date(NaiveDate),
+    Variant(serde_json::Value),
+    TimestampLtz(DateTime<Utc>),
+    TimestampNtz(NaiveDateTime),
+    TimestampTz(DateTime<FixedOffset>),
+    Object(serde_json::Value),
+    Binary(Vec<u8>),
+    Time(NaiveTime),
+    Boolean(bool),
+    Array(serde_json::Value),
+}
+
+impl BindValue {
+    /// Type name as expected by the `bindings` map in the query request
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            // drivers send untyped nulls as text, server casts them to the target type
+            Self::Null | Self::Text(_) => "TEXT",
+            Self::Fixed(_) => "FIXED",
+            Self::Real(_) => "REAL",
+            Self::Date(_) => "DATE",
+            Self::Variant(_) => "VARIANT",
+            Self::TimestampLtz(_) => "TIMESTAMP_LTZ",
+            Self::TimestampNtz(_) => "TIMESTAMP_NTZ",
+            Self::TimestampTz(_) => "TIMESTAMP_TZ",
+            Self::Object(_) => "OBJECT",
+            Self::Binary(_) => "BINARY",
+            Self::Time(_) => "TIME",
+            Self::Boolean(_) => "BOOLEAN",
+            Self::Array(_) => "ARRAY",
+        }
+    }
+
+    /// Serialized value in the wire format used by the official drivers:
+    /// dates are epoch milliseconds, times and timestamps are nanoseconds,
+    /// `TIMESTAMP_TZ` carries offset in minutes shifted by 1440 and binary is hex-encoded.
+    pub fn to_bind_string(&self) -> Option<String> {
+        let value = match self {
+            Self::Null => return None,
+            Self::Fixed(v) => v.to_string(),
+            Self::Real(v) => v.to_string(),
+            Self::Text(v) => v.clone(),
+            Self::Date(v) => {
+                let millis = v.and_time(NaiveTime::MIN).and_utc().timestamp_millis();
+                millis.to_string()
+            }
+            Self::Variant(v) | Self::Object(v) | Self::Array(v) => v.to_string(),
+            Self::TimestampLtz(v) => epoch_nanos(&v.naive_utc()).to_string(),
+            Self::TimestampNtz(v) => epoch_nanos(v).to_string(),
+            Self::TimestampTz(v) => {
+                let offset_minutes = v.offset().local_minus_utc() / 60;
+                format!("{} {}", epoch_nanos(&v.naive_utc()), offset_minutes + 1440)
+            }
+            Self::Binary(v) => hex_encode(v),
+            Self::Time(v) => {
+                let nanos = i64::from(v.num_seconds_from_midnight()) * 1_000_000_000
+                    + i64::from(v.nanosecond());
+                nanos.to_string()
+            }
+            Self::Boolean(v) => v.to_string(),
+        };
+
+        Some(value)
+    }
+
+    pub(crate) fn to_bind_parameter(&self) -> BindParameter {
+        BindParameter {
+            type_: self.type_name().to_string(),
+            value: BindParameterValue::Single(self.to_bind_string()),
+        }
+    }
+
+    /// Textual representation used when binds are uploaded to the stage as CSV,
+    /// in this case server parses values the same way as `COPY INTO` would.
+    fn to_csv_field(&self) -> String {
+        match self {
+            Self::Null => String::new(),
+            Self::Text(v) => quote_csv(v),
+            Self::Date(v) => v.format("%Y-%m-%d").to_string(),
+            Self::Time(v) => v.format("%H:%M:%S%.9f").to_string(),
+            Self::TimestampNtz(v) => v.format("%Y-%m-%d %H:%M:%S%.9f").to_string(),
+            Self::TimestampLtz(v) => v.format("%Y-%m-%d %H:%M:%S%.9f %:z").to_string(),
+            Self::TimestampTz(v) => v.format("%Y-%m-%d %H:%M:%S%.9f %:z").to_string(),
+            Self::Variant(v) | Self::Object(v) | Self::Array(v) => quote_csv(&v.to_string()),
+            Self::Fixed(_) | Self::Real(_) | Self::Binary(_) | Self::Boolean(_) => {
+                self.to_bind_string().unwrap_or_default()
+            }
+        }
+    }
+}
+
+fn quote_csv(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+// i128 as nanoseconds since epoch overflow i64 outside of 1677-2262 years range
+fn epoch_nanos(ts: &NaiveDateTime) -> i128 {
+    let ts = ts.and_utc();
+    i128::from(ts.timestamp()) * 1_000_000_000 + i128::from(ts.timestamp_subsec_nanos())
+}
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut acc, b| {
+            let _ = write!(acc, "{b:02X}");
+            acc
+        })
+}
+
+/// Parameters bound to a single statement.
+/// Positional parameters are numbered from 1 in the order given.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindParams {
+    Positional(Vec<BindValue>),
+    Named(HashMap<String, BindValue>),
+}
+
+impl Default for BindParams {
+    fn default() -> Self {
+        Self::Positional(vec![])
+    }
+}
+
+impl BindParams {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Positional(values) => values.is_empty(),
+            Self::Named(values) => values.is_empty(),
+        }
+    }
+
+    /// Builds `bindings` map for the query request, `None` if there is nothing to bind
+    pub(crate) fn to_bindings(&self) -> Option<HashMap<String, BindParameter>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let bindings = match self {
+            Self::Positional(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| ((i + 1).to_string(), v.to_bind_parameter()))
+                .collect(),
+            Self::Named(values) => values
+                .iter()
+                .map(|(name, v)| (name.clone(), v.to_bind_parameter()))
+                .collect(),
+        };
+
+        Some(bindings)
+    }
+}
+
+impl From<Vec<BindValue>> for BindParams {
+    fn from(value: Vec<BindValue>) -> Self {
+        Self::Positional(value)
+    }
+}
+
+impl From<HashMap<String, BindValue>> for BindParams {
+    fn from(value: HashMap<String, BindValue>) -> Self {
+        Self::Named(value)
+    }
+}
+
+/// Builds `bindings` map for array binding, where each column holds values of a single placeholder.
+/// All the columns must be of the same length and hold values of the same type.
+pub(crate) fn to_array_bindings(
+    columns: &[Vec<BindValue>],
+) -> Result<HashMap<String, BindParameter>, SnowflakeApiError> {
+    validate_columns(columns)?;
+
+    let mut bindings = HashMap::with_capacity(columns.len());
+    for (i, column) in columns.iter().enumerate() {
+        let type_name = column_type_name(column).ok_or_else(|| {
+            SnowflakeApiError::InvalidBindings(format!(
+                "values of column {} are of different types",
+                i + 1
+            ))
+        })?;
+        let values = column.iter().map(BindValue::to_bind_string).collect();
+
+        bindings.insert(
+            (i + 1).to_string(),
+            BindParameter {
+                type_: type_name.to_string(),
+                value: BindParameterValue::Array(values),
+            },
+        );
+    }
+
+    Ok(bindings)
+}
+
+/// Number of values to be bound, this is what drivers compare against the stage binding threshold
+pub(crate) fn array_bindings_count(columns: &[Vec<BindValue>]) -> usize {
+    columns.iter().map(Vec::len).sum()
+}
+
+/// Serializes columns into row-oriented CSV, which is uploaded to the bind stage
+pub(crate) fn to_array_bindings_csv(
+    columns: &[Vec<BindValue>],
+) -> Result<String, SnowflakeApiError> {
+    validate_columns(columns)?;
+
+    let rows = columns.first().map_or(0, Vec::len);
+    let mut csv = String::new();
+    for row in 0..rows {
+        let fields: Vec<String> = columns.iter().map(|c| c[row].to_csv_field()).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    Ok(csv)
+}
+
+fn validate_columns(columns: &[Vec<BindValue>]) -> Result<(), SnowflakeApiError> {
+    let rows = columns.first().map_or(0, Vec::len);
+    if rows == 0 {
+        return Err(SnowflakeApiError::InvalidBindings(
+            "array binding requires at least one row".to_string(),
+        ));
+    }
+    if columns.iter().any(|c| c.len() != rows) {
+        return Err(SnowflakeApiError::InvalidBindings(
+            "all the columns must have the same number of rows".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Type of the non-null values in the column, `None` if they differ
+fn column_type_name(column: &[BindValue]) -> Option<&'static str> {
+    let mut types = column
+        .iter()
+        .filter(|v| **v != BindValue::Null)
+        .map(BindValue::type_name);
+    let first = types.next().unwrap_or("TEXT");
+
+    types.all(|t| t == first).then_some(first)
+}
+
+/// Converts record batch into bind columns, one per batch column.
+/// Types without direct Snowflake counterpart (eg decimals) are bound as their textual representation.
+pub(crate) fn columns_from_record_batch(
+    batch: &RecordBatch,
+) -> Result<Vec<Vec<BindValue>>, ArrowError> {
+    batch
+        .columns()
+        .iter()
+        .map(|c| column_from_array(c.as_ref()))
+        .collect()
+}
+
+fn column_from_array(array: &dyn Array) -> Result<Vec<BindValue>, ArrowError> {
+    macro_rules! primitive {
+        ($t:ty) => {{
+            let a = array.as_primitive::<$t>();
+            collect_values(array, |i| Some(a.value(i).into()))
+        }};
+    }
+    macro_rules! temporal {
+        ($t:ty, $f:ident, $variant:expr) => {{
+            let a = array.as_primitive::<$t>();
+            collect_values(array, |i| a.$f(i).map($variant))
+        }};
+    }
+
+    let values = match array.data_type() {
+        DataType::Boolean => {
+            let a = array.as_boolean();
+            collect_values(array, |i| Some(a.value(i).into()))
+        }
+        DataType::Int8 => primitive!(Int8Type),
+        DataType::Int16 => primitive!(Int16Type),
+        DataType::Int32 => primitive!(Int32Type),
+        DataType::Int64 => primitive!(Int64Type),
+        DataType::UInt8 => primitive!(UInt8Type),
+        DataType::UInt16 => primitive!(UInt16Type),
+        DataType::UInt32 => primitive!(UInt32Type),
+        DataType::UInt64 => {
+            // values of a column must be of the same type, so it's text if any of them overflows
+            let a = array.as_primitive::<UInt64Type>();
+            if a.iter().flatten().all(|v| i64::try_from(v).is_ok()) {
+                collect_values(array, |i| i64::try_from(a.value(i)).ok().map(Into::into))
+            } else {
+                collect_values(array, |i| Some(a.value(i).to_string().into()))
+            }
+        }
+        DataType::Float32 => primitive!(Float32Type),
+        DataType::Float64 => primitive!(Float64Type),
+        DataType::Utf8 => {
+            let a = array.as_string::<i32>();
+            collect_values(array, |i| Some(a.value(i).into()))
+        }
+        DataType::LargeUtf8 => {
+            let a = array.as_string::<i64>();
+            collect_values(array, |i| Some(a.value(i).into()))
+        }
+        DataType::Binary => {
+            let a = array.as_binary::<i32>();
+            collect_values(array, |i| Some(a.value(i).into()))
+        }
+        DataType::LargeBinary => {
+            let a = array.as_binary::<i64>();
+            collect_values(array, |i| Some(a.value(i).into()))
+        }
+        DataType::Date32 => temporal!(Date32Type, value_as_date, BindValue::Date),
+        DataType::Date64 => temporal!(Date64Type, value_as_date, BindValue::Date),
+        DataType::Time32(TimeUnit::Second) => {
+            temporal!(Time32SecondType, value_as_time, BindValue::Time)
+        }
+        DataType::Time32(TimeUnit::Millisecond) => {
+            temporal!(Time32MillisecondType, value_as_time, BindValue::Time)
+        }
+        DataType::Time64(TimeUnit::Microsecond) => {
+            temporal!(Time64MicrosecondType, value_as_time, BindValue::Time)
+        }
+        DataType::Time64(TimeUnit::Nanosecond) => {
+            temporal!(Time64NanosecondType, value_as_time, BindValue::Time)
+        }
+        DataType::Timestamp(unit, tz) => timestamp_column(array, *unit, tz.is_some()),
+        _ => {
+            let formatter = ArrayFormatter::try_new(array, &FormatOptions::default())?;
+            collect_values(array, |i| Some(formatter.value(i).to_string().into()))
+        }
+    };
+
+    Ok(values)
+}
+
+fn timestamp_column(array: &dyn Array, unit: TimeUnit, tz_aware: bool) -> Vec<BindValue> {
+    // timezone-aware timestamps are stored as UTC instants
+    let to_bind_value = |ts: NaiveDateTime| {
+        if tz_aware {
+            BindValue::TimestampLtz(Utc.from_utc_datetime(&ts))
+        } else {
+            BindValue::TimestampNtz(ts)
+        }
+    };
+    macro_rules! timestamp {
+        ($t:ty) => {{
+            let a = array.as_primitive::<$t>();
+            collect_values(array, |i| a.value_as_datetime(i).map(to_bind_value))
+        }};
+    }
+
+    match unit {
+        TimeUnit::Second => timestamp!(TimestampSecondType),
+        TimeUnit::Millisecond => timestamp!(TimestampMillisecondType),
+        TimeUnit::Microsecond => timestamp!(TimestampMicrosecondType),
+        TimeUnit::Nanosecond => timestamp!(TimestampNanosecondType),
+    }
+}
+
+fn collect_values(array: &dyn Array, value: impl Fn(usize) -> Option<BindValue>) -> Vec<BindValue> {
+    (0..array.len())
+        .map(|i| {
+            if array.is_null(i) {
+                BindValue::Null
+            } else {
+                value(i).unwrap_or(BindValue::Null)
+            }
+        })
+        .collect()
+}
+
+macro_rules! impl_bind_value_from {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$t> for BindValue {
+                fn from(value: $t) -> Self {
+                    Self::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_bind_value_from!(
+    i8 => Fixed,
+    i16 => Fixed,
+    i32 => Fixed,
+    i64 => Fixed,
+    u8 => Fixed,
+    u16 => Fixed,
+    u32 => Fixed,
+    f32 => Real,
+    f64 => Real,
+    String => Text,
+    &str => Text,
+    bool => Boolean,
+    NaiveDate => Date,
+    NaiveTime => Time,
+    NaiveDateTime => TimestampNtz,
+    DateTime<Utc> => TimestampLtz,
+    DateTime<FixedOffset> => TimestampTz,
+    Vec<u8> => Binary,
+    &[u8] => Binary,
+);
+
+impl<T: Into<BindValue>> From<Option<T>> for BindValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::UInt64Array;
+    use arrow::record_batch::RecordBatch;
+    use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+
+    use super::{
+        columns_from_record_batch, to_array_bindings, to_array_bindings_csv, BindParams, BindValue,
+    };
+    use crate::requests::BindParameterValue;
+
+    #[test]
+    fn test_bind_wire_format() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let time = NaiveTime::from_hms_nano_opt(1, 2, 3, 4).unwrap();
+        let ts = date.and_time(time);
+        let tz = chrono::FixedOffset::east_opt(2 * 3600)
+            .unwrap()
+            .from_utc_datetime(&ts);
+
+        let cases = [
+            (BindValue::Fixed(42), "FIXED", Some("42")),
+            (BindValue::Real(1.5), "REAL", Some("1.5")),
+            (BindValue::Null, "TEXT", None),
+            (BindValue::Boolean(true), "BOOLEAN", Some("true")),
+            (BindValue::Date(date), "DATE", Some("1704153600000")),
+            (BindValue::Time(time), "TIME", Some("3723000000004")),
+            (
+                BindValue::TimestampNtz(ts),
+                "TIMESTAMP_NTZ",
+                Some("1704157323000000004"),
+            ),
+            (
+                BindValue::TimestampLtz(Utc.from_utc_datetime(&ts)),
+                "TIMESTAMP_LTZ",
+                Some("1704157323000000004"),
+            ),
+            (
+                BindValue::TimestampTz(tz),
+                "TIMESTAMP_TZ",
+                Some("1704157323000000004 1560"),
+            ),
+            (BindValue::Binary(vec![0xde, 0xad]), "BINARY", Some("DEAD")),
+            (
+                BindValue::Variant(serde_json::json!({"a": 1})),
+                "VARIANT",
+                Some(r#"{"a":1}"#),
+            ),
+        ];
+
+        for (value, type_name, expected) in cases {
+            let param = value.to_bind_parameter();
+            assert_eq!(param.type_, type_name);
+            assert_eq!(
+                param.value,
+                BindParameterValue::Single(expected.map(str::to_string))
+            );
+        }
+    }
+
+    #[test]
+    fn test_positional_bindings_are_numbered_from_one() {
+        let params = BindParams::from(vec![BindValue::from(1), BindValue::from("a")]);
+        let bindings = params.to_bindings().unwrap();
+
+        assert_eq!(bindings["1"].type_, "FIXED");
+        assert_eq!(
+            bindings["2"].value,
+            BindParameterValue::Single(Some("a".to_string()))
+        );
+        assert!(BindParams::default().to_bindings().is_none());
+    }
+
+    #[test]
+    fn test_array_bindings() {
+        let columns = vec![
+            vec![BindValue::from(1), BindValue::Null],
+            vec![BindValue::from("a \"quoted\""), BindValue::from("")],
+        ];
+
+        let bindings = to_array_bindings(&columns).unwrap();
+        assert_eq!(bindings["1"].type_, "FIXED");
+        assert_eq!(
+            bindings["1"].value,
+            BindParameterValue::Array(vec![Some("1".to_string()), None])
+        );
+
+        let csv = to_array_bindings_csv(&columns).unwrap();
+        assert_eq!(csv, "1,\"a \"\"quoted\"\"\"\n,\"\"\n");
+
+        let mixed = vec![vec![BindValue::from(1), BindValue::from("a")]];
+        assert!(to_array_bindings(&mixed).is_err());
+        let ragged = vec![vec![BindValue::from(1)], vec![]];
+        assert!(to_array_bindings(&ragged).is_err());
+    }
+
+    #[test]
+    fn test_uint64_column_is_bound_as_single_type() {
+        let batch = |values: Vec<Option<u64>>| {
+            let array: arrow::array::ArrayRef = Arc::new(UInt64Array::from(values));
+            RecordBatch::try_from_iter([("N", array)]).unwrap()
+        };
+
+        let columns = columns_from_record_batch(&batch(vec![Some(1), None])).unwrap();
+        assert_eq!(columns[0], vec![BindValue::Fixed(1), BindValue::Null]);
+        assert_eq!(to_array_bindings(&columns).unwrap()["1"].type_, "FIXED");
+
+        // value above `i64::MAX` turns the whole column into text
+        let columns =
+            columns_from_record_batch(&batch(vec![Some(1), Some(u64::MAX), None])).unwrap();
+        let bindings = to_array_bindings(&columns).unwrap();
+        assert_eq!(bindings["1"].type_, "TEXT");
+        assert_eq!(
+            bindings["1"].value,
+            BindParameterValue::Array(vec![
+                Some("1".to_string()),
+                Some(u64::MAX.to_string()),
+                None
+            ])
+        );
+    }
+}
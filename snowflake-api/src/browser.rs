@@ -0,0 +1,325 @@
+use std::io;
+use std::net::Ipv4Addr;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+
+/// How long to wait for the user to complete the login in the browser, same as in the official drivers
+#[allow(unknown_lints, clippy::duration_suboptimal_units)] // `from_mins` needs Rust 1.91
+pub(crate) const BROWSER_LOGIN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Browsers may open connections they don't send anything over, eg preconnects,
+/// those must not hold up the connection carrying the token
+const CALLBACK_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound for the request sent by the browser, token is either in the query or in the form body
+const MAX_REQUEST_SIZE: usize = 64 * 1024;
+
+const CONFIRMATION_PAGE: &str = "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"/>\
+    <title>SAML Response for Snowflake</title></head><body>\
+    Your identity was confirmed and propagated to Snowflake Rust connector. \
+    You can close this window now and go back where you started from.\
+    </body></html>";
+
+/// Open the URL in the default browser. It's printed as well, in case the browser can't be opened.
+/// The launcher isn't waited for, its exit status is only logged.
+pub(crate) fn open_browser(url: &str) {
+    eprintln!(
+        "Initiating login request with your identity provider. \
+        A browser window should have opened for you to complete the login. \
+        If you can't see it, check existing browser windows, or go to: {url}"
+    );
+
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let child = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                match child.wait().await {
+                    Ok(status) if status.success() => {}
+                    Ok(status) => log::warn!("Failed to open the browser: {status}"),
+                    Err(e) => log::warn!("Failed to open the browser: {e}"),
+                }
+            });
+        }
+        Err(e) => log::warn!("Failed to open the browser: {e}"),
+    }
+}
+
+/// Localhost endpoint the identity provider redirects the browser to, with the SAML token
+pub(crate) struct SsoListener {
+    listener: TcpListener,
+}
+
+impl SsoListener {
+    /// Listen on a random port of the loopback interface
+    pub async fn bind() -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        Ok(Self { listener })
+    }
+
+    pub fn port(&self) -> io::Result<u16> {
+        Ok(self.listener.local_addr()?.port())
+    }
+
+    /// Wait for the browser request carrying the token,
+    /// other requests (eg favicon or CORS preflight) are answered and skipped
+    pub async fn receive_token(&self) -> io::Result<String> {
+        loop {
+            let (mut stream, _) = self.listener.accept().await?;
+            let request = match tokio::time::timeout(
+                CALLBACK_READ_TIMEOUT,
+                CallbackRequest::read(&mut stream),
+            )
+            .await
+            {
+                Ok(Ok(request)) => request,
+                Ok(Err(e)) => {
+                    log::debug!("Ignoring malformed SSO callback request: {e}");
+                    continue;
+                }
+                Err(_) => {
+                    log::debug!("Ignoring idle SSO callback connection");
+                    continue;
+                }
+            };
+
+            if request.method == "OPTIONS" {
+                request.respond(&mut stream, "200 OK", "").await;
+            } else if let Some(token) = request.token() {
+                request
+                    .respond(&mut stream, "200 OK", CONFIRMATION_PAGE)
+                    .await;
+                return Ok(token);
+            } else {
+                request.respond(&mut stream, "400 Bad Request", "").await;
+            }
+        }
+    }
+}
+
+struct CallbackRequest {
+    method: String,
+    target: String,
+    /// Names are lowercase
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl CallbackRequest {
+    async fn read(stream: &mut TcpStream) -> io::Result<Self> {
+        let mut buf = Vec::new();
+        let head_len = loop {
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            read_more(stream, &mut buf).await?;
+        };
+
+        let head = std::str::from_utf8(&buf[..head_len])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let method = request_line.next().unwrap_or_default().to_string();
+        let target = request_line.next().unwrap_or_default().to_string();
+        let headers: Vec<_> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let mut request = Self {
+            method,
+            target,
+            headers,
+            body: buf.split_off(head_len),
+        };
+        let content_length = request
+            .header("content-length")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        while request.body.len() < content_length {
+            read_more(stream, &mut request.body).await?;
+        }
+        request.body.truncate(content_length);
+
+        Ok(request)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn token(&self) -> Option<String> {
+        let query = self.target.split_once('?').map_or("", |(_, query)| query);
+        url::form_urlencoded::parse(query.as_bytes())
+            .chain(url::form_urlencoded::parse(&self.body))
+            .find(|(name, _)| name == "token")
+            .map(|(_, token)| token.into_owned())
+    }
+
+    /// Failures are only logged, browser isn't waiting for anything else
+    async fn respond(&self, stream: &mut TcpStream, status: &str, body: &str) {
+        let cors = self
+            .header("origin")
+            .map(|origin| {
+                format!(
+                    "Access-Control-Allow-Origin: {origin}\r\n\
+                    Access-Control-Allow-Methods: POST, GET\r\n\
+                    Access-Control-Allow-Headers: {}\r\n\
+                    Vary: Accept-Encoding, Origin\r\n",
+                    self.header("access-control-request-headers")
+                        .unwrap_or_default()
+                )
+            })
+            .unwrap_or_default();
+        let response = format!(
+            "HTTP/1.1 {status}\r\n\
+            Content-Type: text/html\r\n\
+            Content-Length: {}\r\n\
+            Connection: close\r\n\
+            {cors}\r\n\
+            {body}",
+            body.len()
+        );
+
+        if let Err(e) = stream.write_all(response.as_bytes()).await {
+            log::debug!("Failed to respond to the SSO callback request: {e}");
+        }
+        let _ = stream.shutdown().await;
+    }
+}
+
+async fn read_more(stream: &mut TcpStream, buf: &mut Vec<u8>) -> io::Result<()> {
+    if buf.len() > MAX_REQUEST_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "SSO callback request is too large",
+        ));
+    }
+    let mut chunk = [0; 4096];
+    let n = stream.read(&mut chunk).await?;
+    if n == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    buf.extend_from_slice(&chunk[..n]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_token_received_from_idp_redirect() {
+        let listener = SsoListener::bind().await.unwrap();
+        let port = listener.port().unwrap();
+
+        // identity provider redirects the browser back to the listener once user is logged in
+        let idp = MockServer::start().await;
+        Mock::given(path("/sso"))
+            .respond_with(ResponseTemplate::new(302).insert_header(
+                "Location",
+                format!("http://127.0.0.1:{port}/?token=saml%2Btoken&confirm=true"),
+            ))
+            .mount(&idp)
+            .await;
+
+        let browser = tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            let favicon = client
+                .get(format!("http://127.0.0.1:{port}/favicon.ico"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(favicon.status(), 400);
+            let page = client
+                .get(format!("{}/sso", idp.uri()))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(page.status(), 200);
+            page.text().await.unwrap()
+        });
+
+        let token = listener.receive_token().await.unwrap();
+        assert_eq!(token, "saml+token");
+        assert!(browser
+            .await
+            .unwrap()
+            .contains("Your identity was confirmed"));
+    }
+
+    #[tokio::test]
+    async fn test_token_received_from_form_post() {
+        let listener = SsoListener::bind().await.unwrap();
+        let url = format!("http://127.0.0.1:{}/", listener.port().unwrap());
+
+        let browser = tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            let preflight = client
+                .request(reqwest::Method::OPTIONS, &url)
+                .header("Origin", "https://idp.example.com")
+                .header("Access-Control-Request-Headers", "content-type")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(
+                preflight.headers()["access-control-allow-origin"],
+                "https://idp.example.com"
+            );
+            client
+                .post(&url)
+                .form(&[("token", "form-token"), ("confirm", "true")])
+                .send()
+                .await
+                .unwrap()
+                .status()
+        });
+
+        assert_eq!(listener.receive_token().await.unwrap(), "form-token");
+        assert_eq!(browser.await.unwrap(), 200);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_connection_does_not_block_token() {
+        let listener = SsoListener::bind().await.unwrap();
+        let addr = (Ipv4Addr::LOCALHOST, listener.port().unwrap());
+
+        // preconnect which never sends the request
+        let _idle = TcpStream::connect(addr).await.unwrap();
+        let browser = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(b"GET /?token=late-token HTTP/1.1\r\nHost: localhost\r\n\r\n")
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        });
+
+        assert_eq!(listener.receive_token().await.unwrap(), "late-token");
+        assert!(browser.await.unwrap().starts_with("HTTP/1.1 200 OK"));
+    }
+}
@@ -0,0 +1,332 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::connection::{Connection, QueryType};
+use crate::requests::AbortRequest;
+use crate::responses::AbortRequestResponse;
+use crate::session::Session;
+use crate::{SnowflakeApi, SnowflakeApiError};
+
+/// Asks server to abort the statement sent with the given request id
+pub(crate) async fn abort_request(
+    connection: &Connection,
+    session: &Session,
+    account_identifier: &str,
+    request_id: Uuid,
+) -> Result<(), SnowflakeApiError> {
+    log::debug!("Aborting request: {request_id}");
+
+    let resp: AbortRequestResponse = crate::request_with_session(
+        connection,
+        session,
+        account_identifier,
+        QueryType::AbortRequest,
+        |_| AbortRequest {
+            request_id: request_id.to_string(),
+        },
+        None,
+        Uuid::new_v4(),
+    )
+    .await?;
+
+    if resp.success {
+        Ok(())
+    } else {
+        Err(SnowflakeApiError::ApiError {
+            code: resp.code.unwrap_or_default(),
+            message: resp.message.unwrap_or_default(),
+            query_id: String::new(),
+        })
+    }
+}
+
+impl SnowflakeApi {
+    /// Runs the statement sent with the given request id. If cancel on drop is enabled,
+    /// the statement is aborted when this future is dropped before the statement has finished.
+    pub(crate) async fn abort_on_drop<T>(
+        &self,
+        request_id: Uuid,
+        statement: impl Future<Output = T>,
+    ) -> T {
+        let guard = self
+            .cancel_on_drop
+            .then(|| AbortOnDrop::new(self, request_id));
+        // boxed, otherwise the statement is kept twice in this future
+        let res = Box::pin(statement).await;
+
+        // statement has finished one way or another
+        if let Some(guard) = guard {
+            guard.disarm();
+        }
+        res
+    }
+}
+
+/// Aborts the statement on the server if dropped before being disarmed,
+/// eg when the future executing the statement is dropped by a timeout.
+struct AbortOnDrop {
+    connection: Arc<Connection>,
+    session: Arc<Session>,
+    account_identifier: String,
+    request_id: Uuid,
+    armed: bool,
+}
+
+impl AbortOnDrop {
+    fn new(api: &SnowflakeApi, request_id: Uuid) -> Self {
+        Self {
+            connection: Arc::clone(&api.connection),
+            session: Arc::clone(&api.session),
+            account_identifier: api.account_identifier.clone(),
+            request_id,
+            armed: true,
+        }
+    }
+
+    /// Statement has completed, nothing to abort
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            log::warn!(
+                "Can not abort request {} outside of tokio runtime",
+                self.request_id
+            );
+            return;
+        };
+
+        let connection = Arc::clone(&self.connection);
+        let session = Arc::clone(&self.session);
+        let account_identifier = self.account_identifier.clone();
+        let request_id = self.request_id;
+        runtime.spawn(async move {
+            if let Err(e) =
+                abort_request(&connection, &session, &account_identifier, request_id).await
+            {
+                log::warn!("Failed to abort request {request_id}: {e}");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::session::MASTER_TOKEN_EXPIRED;
+    use crate::test_utils::{
+        api, login_response, mount_login, running_response, select_one_response, success_response,
+    };
+    use crate::SnowflakeApiError;
+
+    const QUERY_ID: &str = "01b2c3d4-0000-0001-0000-000000000001";
+
+    async fn mount_abort(server: &MockServer, request_id: &str) {
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/abort-request"))
+            .and(body_partial_json(json!({"requestId": request_id})))
+            .respond_with(success_response(&Value::Null))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    async fn abort_requests(server: &MockServer) -> Vec<Value> {
+        server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.url.path() == "/queries/v1/abort-request")
+            .map(|r| r.body_json().unwrap())
+            .collect()
+    }
+
+    /// Request id the statement was sent with, as a query parameter
+    async fn query_request_id(server: &MockServer) -> String {
+        server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|r| r.url.path() == "/queries/v1/query-request")
+            .and_then(|r| {
+                r.url
+                    .query_pairs()
+                    .find(|(k, _)| k == "requestId")
+                    .map(|(_, v)| v.to_string())
+            })
+            .unwrap()
+    }
+
+    /// Query request which never completes, so the future executing it can only be dropped
+    async fn start_hanging_query() -> MockServer {
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/abort-request"))
+            .respond_with(success_response(&Value::Null))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    /// Abort is sent in the background, once the future is dropped
+    async fn assert_query_aborted(server: &MockServer) {
+        let mut aborted = vec![];
+        for _ in 0..50 {
+            aborted = abort_requests(server).await;
+            if !aborted.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let request_id = query_request_id(server).await;
+        assert_eq!(aborted, vec![json!({"requestId": request_id})]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_submitted_query() {
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .respond_with(running_response(QUERY_ID))
+            .mount(&server)
+            .await;
+
+        let api = api(&server);
+        let handle = api.submit("SELECT SYSTEM$WAIT(60)").await.unwrap();
+        let request_id = query_request_id(&server).await;
+        assert_eq!(handle.request_id().unwrap().to_string(), request_id);
+
+        mount_abort(&server, &request_id).await;
+        handle.cancel().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_restored_handle() {
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        let request_id = Uuid::new_v4();
+        mount_abort(&server, &request_id.to_string()).await;
+
+        let api = api(&server);
+        let err = api.query_handle(QUERY_ID).cancel().await.unwrap_err();
+        assert!(matches!(
+            err,
+            SnowflakeApiError::UnknownRequestId { ref query_id } if query_id == QUERY_ID
+        ));
+
+        api.query_handle(QUERY_ID)
+            .with_request_id(request_id)
+            .cancel()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_on_expired_session() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/v1/login-request"))
+            .respond_with(login_response("session"))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/abort-request"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": MASTER_TOKEN_EXPIRED,
+                "message": "Authentication token has expired.  The user must authenticate again.",
+                "success": false,
+                "data": null
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        let request_id = Uuid::new_v4();
+        mount_abort(&server, &request_id.to_string()).await;
+
+        api(&server).cancel(request_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dropped_statement_is_aborted() {
+        let server = start_hanging_query().await;
+        let mut api = api(&server);
+        api.cancel_on_drop = true;
+        let res = tokio::time::timeout(Duration::from_millis(200), api.exec("SELECT 1")).await;
+        assert!(res.is_err());
+        assert_query_aborted(&server).await;
+    }
+
+    #[tokio::test]
+    async fn test_dropped_stream_multi_statement_and_describe_are_aborted() {
+        let timeout = Duration::from_millis(200);
+
+        let server = start_hanging_query().await;
+        let mut stream_api = api(&server);
+        stream_api.cancel_on_drop = true;
+        let mut stream = Box::pin(stream_api.exec_stream("SELECT 1"));
+        assert!(tokio::time::timeout(timeout, stream.next()).await.is_err());
+        drop(stream);
+        assert_query_aborted(&server).await;
+
+        let server = start_hanging_query().await;
+        let mut multi_api = api(&server);
+        multi_api.cancel_on_drop = true;
+        let res =
+            tokio::time::timeout(timeout, multi_api.exec_multi("SELECT 1; SELECT 2", 2)).await;
+        assert!(res.is_err());
+        assert_query_aborted(&server).await;
+
+        let server = start_hanging_query().await;
+        let mut describe_api = api(&server);
+        describe_api.cancel_on_drop = true;
+        let res = tokio::time::timeout(timeout, describe_api.describe("SELECT 1")).await;
+        assert!(res.is_err());
+        assert_query_aborted(&server).await;
+    }
+
+    #[tokio::test]
+    async fn test_completed_statement_is_not_aborted() {
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .respond_with(select_one_response(QUERY_ID))
+            .mount(&server)
+            .await;
+
+        let mut api = api(&server);
+        api.cancel_on_drop = true;
+        let resp = api.exec("SELECT 1").await.unwrap();
+        assert_eq!(
+            resp.request_id.unwrap().to_string(),
+            query_request_id(&server).await
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(abort_requests(&server).await.is_empty());
+    }
+}
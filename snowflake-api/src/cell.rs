@@ -0,0 +1,308 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::compute::cast;
+use arrow::datatypes::{
+    DataType, Date32Type, Decimal128Type, Field, Float64Type, Int64Type, Time64NanosecondType,
+    TimeUnit, TimestampNanosecondType,
+};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde_json::{Map, Value};
+
+use crate::{normalize_batch, variant, FromSnowflake};
+
+/// Cells of every batch row, batch is normalized first
+pub(crate) fn batch_cells(batch: &RecordBatch) -> Result<Vec<Vec<CellValue>>, ArrowError> {
+    let batch = normalize_batch(batch)?;
+    let schema = batch.schema();
+    let columns = batch
+        .columns()
+        .iter()
+        .map(prepare_column)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    (0..batch.num_rows())
+        .map(|row| {
+            schema
+                .fields()
+                .iter()
+                .zip(&columns)
+                .map(|(field, column)| CellValue::from_array(field, column, row))
+                .collect()
+        })
+        .collect()
+}
+
+/// Cast the column to one of the few types cells are read from
+fn prepare_column(column: &ArrayRef) -> Result<ArrayRef, ArrowError> {
+    let target = match column.data_type() {
+        t if t.is_integer() && *t != DataType::UInt64 => DataType::Int64,
+        t if t.is_floating() => DataType::Float64,
+        DataType::Timestamp(unit, tz) if *unit != TimeUnit::Nanosecond => {
+            DataType::Timestamp(TimeUnit::Nanosecond, tz.clone())
+        }
+        DataType::Time32(_) | DataType::Time64(TimeUnit::Microsecond) => {
+            DataType::Time64(TimeUnit::Nanosecond)
+        }
+        DataType::Date64 => DataType::Date32,
+        DataType::LargeUtf8 | DataType::Utf8View => DataType::Utf8,
+        DataType::LargeBinary | DataType::BinaryView => DataType::Binary,
+        _ => return Ok(Arc::clone(column)),
+    };
+    cast(column, &target)
+}
+
+/// Single value of the result, see [`Row`](crate::Row)
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Null,
+    Boolean(bool),
+    Int(i64),
+    Float(f64),
+    /// Unscaled value, `NUMBER` with scale
+    Decimal {
+        value: i128,
+        scale: i8,
+    },
+    Text(String),
+    Binary(Vec<u8>),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    /// `TIMESTAMP_NTZ`
+    TimestampNtz(NaiveDateTime),
+    /// `TIMESTAMP_LTZ` and `TIMESTAMP_TZ`
+    Timestamp(DateTime<Utc>),
+    /// `VARIANT`, `OBJECT`, `ARRAY`, `MAP`, `VECTOR` and `GeoJSON`
+    Variant(Value),
+}
+
+impl CellValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Name of the variant, for error messages
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean(_) => "boolean",
+            Self::Int(_) => "integer",
+            Self::Float(_) => "float",
+            Self::Decimal { .. } => "decimal",
+            Self::Text(_) => "text",
+            Self::Binary(_) => "binary",
+            Self::Date(_) => "date",
+            Self::Time(_) => "time",
+            Self::TimestampNtz(_) => "timestamp_ntz",
+            Self::Timestamp(_) => "timestamp",
+            Self::Variant(_) => "variant",
+        }
+    }
+
+    fn from_array(field: &Field, array: &ArrayRef, row: usize) -> Result<Self, ArrowError> {
+        if array.is_null(row) {
+            return Ok(Self::Null);
+        }
+
+        let value = match array.data_type() {
+            DataType::Null => Self::Null,
+            DataType::Boolean => Self::Boolean(array.as_boolean().value(row)),
+            DataType::Int64 => Self::Int(array.as_primitive::<Int64Type>().value(row)),
+            DataType::Float64 => Self::Float(array.as_primitive::<Float64Type>().value(row)),
+            DataType::Decimal128(_, scale) => Self::Decimal {
+                value: array.as_primitive::<Decimal128Type>().value(row),
+                scale: *scale,
+            },
+            DataType::Utf8 => Self::from_text(field, array.as_string::<i32>().value(row)),
+            DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(..)
+            | DataType::Struct(_)
+            | DataType::Map(..) => Self::Variant(nested_value(array, row)?),
+            DataType::Binary => Self::Binary(array.as_binary::<i32>().value(row).to_vec()),
+            DataType::Date32 => array
+                .as_primitive::<Date32Type>()
+                .value_as_date(row)
+                .map_or(Self::Null, Self::Date),
+            DataType::Time64(TimeUnit::Nanosecond) => array
+                .as_primitive::<Time64NanosecondType>()
+                .value_as_time(row)
+                .map_or(Self::Null, Self::Time),
+            DataType::Timestamp(TimeUnit::Nanosecond, tz) => {
+                let Some(datetime) = array
+                    .as_primitive::<TimestampNanosecondType>()
+                    .value_as_datetime(row)
+                else {
+                    return Ok(Self::Null);
+                };
+                if tz.is_some() {
+                    Self::Timestamp(datetime.and_utc())
+                } else {
+                    Self::TimestampNtz(datetime)
+                }
+            }
+            _ => {
+                let formatter = ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default())?;
+                Self::Text(formatter.value(row).to_string())
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn from_text(field: &Field, text: &str) -> Self {
+        let json = || {
+            serde_json::from_str(text).map_or_else(|_| Self::Text(text.to_string()), Self::Variant)
+        };
+        match field.metadata().get("logicalType").map(String::as_str) {
+            _ if variant::is_json_field(field) => json(),
+            // GeoJSON output format, WKT and hex WKB are kept as text
+            Some("GEOGRAPHY" | "GEOMETRY") if text.starts_with('{') => json(),
+            Some("DECFLOAT") => parse_decimal(text).map_or_else(
+                || Self::Text(text.to_string()),
+                |(value, scale)| Self::Decimal { value, scale },
+            ),
+            _ => Self::Text(text.to_string()),
+        }
+    }
+}
+
+/// Nested value of the Arrow result as JSON, eg `VECTOR` or structured `MAP`
+fn nested_value(array: &ArrayRef, row: usize) -> Result<Value, ArrowError> {
+    let value = match array.data_type() {
+        DataType::List(field) => list_value(field, &array.as_list::<i32>().value(row))?,
+        DataType::LargeList(field) => list_value(field, &array.as_list::<i64>().value(row))?,
+        DataType::FixedSizeList(field, _) => {
+            list_value(field, &array.as_fixed_size_list().value(row))?
+        }
+        DataType::Struct(fields) => {
+            let mut object = Map::new();
+            for (field, column) in fields.iter().zip(array.as_struct().columns()) {
+                let column = prepare_column(&column.slice(row, 1))?;
+                object.insert(field.name().clone(), json_value(field, &column, 0)?);
+            }
+            Value::Object(object)
+        }
+        DataType::Map(entries, _) => {
+            let DataType::Struct(fields) = entries.data_type() else {
+                return Err(ArrowError::SchemaError("Invalid map entries".to_string()));
+            };
+            let entries = array.as_map().value(row);
+            let keys = prepare_column(entries.column(0))?;
+            let values = prepare_column(entries.column(1))?;
+            let mut object = Map::new();
+            for i in 0..entries.len() {
+                let key = match json_value(&fields[0], &keys, i)? {
+                    Value::String(key) => key,
+                    key => key.to_string(),
+                };
+                object.insert(key, json_value(&fields[1], &values, i)?);
+            }
+            Value::Object(object)
+        }
+        other => {
+            return Err(ArrowError::NotYetImplemented(format!(
+                "Nested type {other} is not supported"
+            )))
+        }
+    };
+    Ok(value)
+}
+
+fn list_value(field: &Field, values: &ArrayRef) -> Result<Value, ArrowError> {
+    let values = prepare_column(values)?;
+    (0..values.len())
+        .map(|i| json_value(field, &values, i))
+        .collect()
+}
+
+/// `array` has to be prepared with [`prepare_column`]
+fn json_value(field: &Field, array: &ArrayRef, row: usize) -> Result<Value, ArrowError> {
+    let cell = CellValue::from_array(field, array, row)?;
+    Ok(Value::from_cell(&cell).unwrap_or_default())
+}
+
+/// Exact decimal from its text, eg `-1.5E+3`, `None` if it doesn't fit into `Decimal128`
+pub(crate) fn parse_decimal(text: &str) -> Option<(i128, i8)> {
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if !frac.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut value: i128 = format!("{int}{frac}").parse().ok()?;
+    let mut scale = i32::try_from(frac.len()).ok()? - exponent;
+    if scale < 0 {
+        value = value.checked_mul(10_i128.checked_pow(scale.unsigned_abs())?)?;
+        scale = 0;
+    }
+    // max precision of `Decimal128`
+    if scale > 38 {
+        return None;
+    }
+    Some((value, i8::try_from(scale).ok()?))
+}
+
+/// Decimal as a string, eg `-1.05`, to be parsed without precision loss
+pub(crate) fn format_decimal(value: i128, scale: i8) -> String {
+    let Ok(scale) = usize::try_from(scale) else {
+        return format!("{value}{}", "0".repeat(usize::from(scale.unsigned_abs())));
+    };
+    let digits = format!("{:0>width$}", value.unsigned_abs(), width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    let sign = if value < 0 { "-" } else { "" };
+    if frac.is_empty() {
+        format!("{sign}{int}")
+    } else {
+        format!("{sign}{int}.{frac}")
+    }
+}
+
+impl Display for CellValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "NULL"),
+            Self::Boolean(v) => write!(f, "{v}"),
+            Self::Int(v) => write!(f, "{v}"),
+            Self::Float(v) => write!(f, "{v}"),
+            Self::Decimal { value, scale } => write!(f, "{}", format_decimal(*value, *scale)),
+            Self::Text(v) => write!(f, "{v}"),
+            Self::Binary(v) => write!(f, "{}", crate::bindings::hex_encode(v)),
+            Self::Date(v) => write!(f, "{v}"),
+            Self::Time(v) => write!(f, "{v}"),
+            Self::TimestampNtz(v) => write!(f, "{v}"),
+            Self::Timestamp(v) => write!(f, "{v}"),
+            Self::Variant(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_decimal() {
+        assert_eq!(format_decimal(12345, 2), "123.45");
+        assert_eq!(format_decimal(-5, 3), "-0.005");
+        assert_eq!(format_decimal(42, 0), "42");
+        assert_eq!(format_decimal(42, -2), "4200");
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("123.45"), Some((12345, 2)));
+        assert_eq!(parse_decimal("-1.5E+3"), Some((-1500, 0)));
+        assert_eq!(parse_decimal("25e-3"), Some((25, 3)));
+        assert_eq!(parse_decimal("1E+100"), None);
+        assert_eq!(parse_decimal("1.2.3"), None);
+        assert_eq!(parse_decimal("abc"), None);
+    }
+}
@@ -39,21 +39,29 @@ struct QueryContext {
     accept_mime: &'static str,
 }
 
+#[derive(Clone, Copy)]
 pub enum QueryType {
     LoginRequest,
+    AuthenticatorRequest,
     TokenRequest,
     CloseSession,
     JsonQuery,
     ArrowQuery,
+    AbortRequest,
+    Heartbeat,
 }
 
 impl QueryType {
-    const fn query_context(&self) -> QueryContext {
+    const fn query_context(self) -> QueryContext {
         match self {
             Self::LoginRequest => QueryContext {
                 path: "session/v1/login-request",
                 accept_mime: "application/json",
             },
+            Self::AuthenticatorRequest => QueryContext {
+                path: "session/authenticator-request",
+                accept_mime: "application/json",
+            },
             Self::TokenRequest => QueryContext {
                 path: "/session/token-request",
                 accept_mime: "application/snowflake",
@@ -70,6 +78,14 @@ impl QueryType {
                 path: "queries/v1/query-request",
                 accept_mime: "application/snowflake",
             },
+            Self::AbortRequest => QueryContext {
+                path: "queries/v1/abort-request",
+                accept_mime: "application/json",
+            },
+            Self::Heartbeat => QueryContext {
+                path: "session/heartbeat",
+                accept_mime: "application/json",
+            },
         }
     }
 }
@@ -79,6 +95,7 @@ impl QueryType {
 pub struct Connection {
     // no need for Arc as it's already inside the reqwest client
     client: ClientWithMiddleware,
+    base_url: Option<String>,
 }
 
 impl Connection {
@@ -99,7 +116,18 @@ impl Connection {
     /// ```
     /// This is not intended to be called directly, but is used by `SnowflakeApiBuilder::with_client`
     pub fn new_with_middware(client: ClientWithMiddleware) -> Self {
-        Self { client }
+        Self {
+            client,
+            base_url: None,
+        }
+    }
+
+    /// Send requests to the given URL instead of `https://{account}.snowflakecomputing.com`,
+    /// eg to the proxy or the mock server
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.trim_end_matches('/').to_string());
+        self
     }
 
     pub fn default_client_builder() -> Result<reqwest_middleware::ClientBuilder, ConnectionError> {
@@ -120,9 +148,33 @@ impl Connection {
     }
 
     /// Perform request of given query type with extra body or parameters
+    pub async fn request<R: serde::de::DeserializeOwned>(
+        &self,
+        query_type: QueryType,
+        account_identifier: &str,
+        extra_get_params: &[(&str, &str)],
+        auth: Option<&str>,
+        body: impl serde::Serialize,
+        url_override: Option<&str>,
+    ) -> Result<R, ConnectionError> {
+        self.request_with_id(
+            query_type,
+            account_identifier,
+            extra_get_params,
+            auth,
+            body,
+            url_override,
+            Uuid::new_v4(),
+        )
+        .await
+    }
+
+    /// Same as [`Connection::request`], but with request id known to the caller,
+    /// which is needed to abort the request later on
     // todo: implement soft error handling
     // todo: is there better way to not repeat myself?
-    pub async fn request<R: serde::de::DeserializeOwned>(
+    #[allow(clippy::too_many_arguments)]
+    pub async fn request_with_id<R: serde::de::DeserializeOwned>(
         &self,
         query_type: QueryType,
         account_identifier: &str,
@@ -130,6 +182,7 @@ impl Connection {
         auth: Option<&str>,
         body: impl serde::Serialize,
         url_override: Option<&str>,
+        request_id: Uuid,
     ) -> Result<R, ConnectionError> {
         let context = query_type.query_context();
         let mut headers = HeaderMap::new();
@@ -139,10 +192,11 @@ impl Connection {
             HeaderValue::from_static(context.accept_mime),
         );
 
-        let base_url = format!(
-            "https://{}.snowflakecomputing.com/{}",
-            &account_identifier, context.path
-        );
+        let path = context.path.trim_start_matches('/');
+        let base_url = match &self.base_url {
+            Some(base_url) => format!("{base_url}/{path}"),
+            None => format!("https://{account_identifier}.snowflakecomputing.com/{path}"),
+        };
         if let Some(auth) = auth {
             let mut auth_val = HeaderValue::from_str(auth)?;
             auth_val.set_sensitive(true);
@@ -150,7 +204,6 @@ impl Connection {
         }
         let resp = match url_override {
             None => {
-                let request_id = Uuid::new_v4();
                 let request_guid = Uuid::new_v4();
                 let client_start_time = SystemTime::now()
                     .duration_since(UNIX_EPOCH)
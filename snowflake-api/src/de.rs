@@ -0,0 +1,495 @@
+//! Deserialization of the result rows into user types with serde.
+//! Every row is a map of column name to the cell value, so structs are filled by column name,
+//! tuples and sequences by column order.
+
+use std::fmt::{Display, Formatter};
+
+use arrow::record_batch::RecordBatch;
+use serde::de::value::{SeqDeserializer, StringDeserializer};
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserializer};
+
+use crate::cell::{self, format_decimal};
+use crate::{CellValue, QueryResult, SnowflakeApiError};
+
+impl QueryResult {
+    /// Deserialize every row of the result into `T`.
+    /// Struct fields are matched to the columns by name, falling back to case-insensitive match,
+    /// as unquoted Snowflake identifiers are uppercase.
+    /// Numbers with scale could be read as `rust_decimal::Decimal` or `f64`, timestamps as `chrono`
+    /// types and `VARIANT`, `OBJECT` and `ARRAY` as `serde_json::Value` or any other deserializable type.
+    pub fn deserialize_rows<T: DeserializeOwned>(&self) -> Result<Vec<T>, SnowflakeApiError> {
+        match self {
+            QueryResult::Arrow(result) => {
+                let mut rows = vec![];
+                for batch in &result.batches {
+                    let batch_rows = deserialize_batch(batch, rows.len())?;
+                    rows.extend(batch_rows);
+                }
+                Ok(rows)
+            }
+            QueryResult::Json(result) => deserialize_batch(&result.to_record_batch()?, 0),
+            QueryResult::Empty(_) => Ok(vec![]),
+        }
+    }
+}
+
+/// `first_row` is the index of the first batch row in the whole result, used in errors
+pub(crate) fn deserialize_batch<T: DeserializeOwned>(
+    batch: &RecordBatch,
+    first_row: usize,
+) -> Result<Vec<T>, SnowflakeApiError> {
+    let names: Vec<String> = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect();
+
+    cell::batch_cells(batch)?
+        .into_iter()
+        .enumerate()
+        .map(|(row, cells)| {
+            T::deserialize(RowDeserializer {
+                names: &names,
+                cells,
+            })
+            .map_err(|e| SnowflakeApiError::RowDeserializationError {
+                row: first_row + row,
+                column: e.column,
+                message: e.message,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub(crate) struct DeError {
+    column: Option<String>,
+    message: String,
+}
+
+impl DeError {
+    fn in_column(mut self, column: &str) -> Self {
+        self.column.get_or_insert_with(|| column.to_string());
+        self
+    }
+}
+
+impl Display for DeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl serde::de::Error for DeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            column: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Single cell, `CellValue` is public so the deserializer is kept private
+struct CellDeserializer(CellValue);
+
+/// `NUMBER` without scale arrives as `Decimal` when its precision doesn't fit into `Int64`,
+/// eg the default `NUMBER(38, 0)`, it's read as integer if the value fits into the target type
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                match self.0 {
+                    CellValue::Decimal { value, scale: 0 } => {
+                        let value = <$ty>::try_from(value).map_err(|_| {
+                            serde::de::Error::custom(format!(
+                                "number {value} is out of range for {}",
+                                stringify!($ty)
+                            ))
+                        })?;
+                        visitor.$visit(value)
+                    }
+                    value => Self(value).deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for CellDeserializer {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            CellValue::Null => visitor.visit_unit(),
+            CellValue::Boolean(v) => visitor.visit_bool(v),
+            CellValue::Int(v) => visitor.visit_i64(v),
+            CellValue::Float(v) => visitor.visit_f64(v),
+            CellValue::Decimal { value, scale } => {
+                visitor.visit_string(format_decimal(value, scale))
+            }
+            CellValue::Text(v) => visitor.visit_string(v),
+            CellValue::Binary(v) => visitor.visit_byte_buf(v),
+            // formats `chrono` types are deserialized from
+            CellValue::Date(v) => visitor.visit_string(v.to_string()),
+            CellValue::Time(v) => visitor.visit_string(v.to_string()),
+            CellValue::TimestampNtz(v) => {
+                visitor.visit_string(v.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+            }
+            CellValue::Timestamp(v) => visitor.visit_string(v.to_rfc3339()),
+            CellValue::Variant(v) => v.deserialize_any(visitor).map_err(serde::de::Error::custom),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            CellValue::Null => visitor.visit_none(),
+            value => visitor.visit_some(Self(value)),
+        }
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            CellValue::Decimal { value, scale } => {
+                let float = format_decimal(value, scale)
+                    .parse()
+                    .map_err(serde::de::Error::custom)?;
+                visitor.visit_f64(float)
+            }
+            value => Self(value).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_f64(visitor)
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            // eg `Vec<u8>`
+            CellValue::Binary(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+            value => Self(value).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        match self.0 {
+            CellValue::Text(v) => visitor.visit_enum(StringDeserializer::<DeError>::new(v)),
+            CellValue::Variant(v) => v
+                .deserialize_enum(name, variants, visitor)
+                .map_err(serde::de::Error::custom),
+            value => Self(value).deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Row as a map of column names to cells
+struct RowDeserializer<'a> {
+    names: &'a [String],
+    cells: Vec<CellValue>,
+}
+
+impl<'de> Deserializer<'de> for RowDeserializer<'_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let keys = self.names.iter().map(|name| (name.clone(), name.as_str()));
+        visitor.visit_map(RowAccess::new(keys.collect(), self.cells))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        let keys = self.names.iter().map(|name| {
+            let key = fields
+                .iter()
+                .find(|f| *f == name)
+                .or_else(|| fields.iter().find(|f| f.eq_ignore_ascii_case(name)))
+                .map_or_else(|| name.clone(), |f| (*f).to_string());
+            (key, name.as_str())
+        });
+        visitor.visit_map(RowAccess::new(keys.collect(), self.cells))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let keys = self.names.iter().map(|name| (name.clone(), name.as_str()));
+        visitor.visit_seq(RowAccess::new(keys.collect(), self.cells))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct enum identifier ignored_any
+    }
+}
+
+/// Cells with their keys and column names, column name is attached to the cell errors
+struct RowAccess<'a> {
+    entries: std::iter::Zip<std::vec::IntoIter<(String, &'a str)>, std::vec::IntoIter<CellValue>>,
+    value: Option<(&'a str, CellValue)>,
+}
+
+impl<'a> RowAccess<'a> {
+    fn new(keys: Vec<(String, &'a str)>, cells: Vec<CellValue>) -> Self {
+        Self {
+            entries: keys.into_iter().zip(cells),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for RowAccess<'_> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        let Some(((key, column), cell)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some((column, cell));
+        seed.deserialize(key.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        let (column, cell) = self
+            .value
+            .take()
+            .ok_or_else(|| serde::de::Error::custom("value is missing"))?;
+        seed.deserialize(CellDeserializer(cell))
+            .map_err(|e| e.in_column(column))
+    }
+}
+
+impl<'de> SeqAccess<'de> for RowAccess<'_> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        let Some(((_, column), cell)) = self.entries.next() else {
+            return Ok(None);
+        };
+        seed.deserialize(CellDeserializer(cell))
+            .map(Some)
+            .map_err(|e| e.in_column(column))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rust_decimal::Decimal;
+    use serde::Deserialize;
+
+    use chrono::{DateTime, NaiveDate, Utc};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::JsonResult;
+
+    fn json_result(rowtype: Value, rows: Value) -> QueryResult {
+        QueryResult::Json(JsonResult::from_rowtype(rowtype, rows))
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Order {
+        id: i64,
+        price: Decimal,
+        ratio: Option<f64>,
+        placed_at: DateTime<Utc>,
+        day: NaiveDate,
+        payload: Value,
+        #[serde(rename = "Note")]
+        note: Option<String>,
+    }
+
+    #[test]
+    fn test_deserialize_rows_into_struct() {
+        let result = json_result(
+            serde_json::json!([
+                {"name": "ID", "type": "fixed", "precision": 18, "scale": 0, "nullable": false},
+                {"name": "PRICE", "type": "fixed", "precision": 10, "scale": 2, "nullable": true},
+                {"name": "RATIO", "type": "fixed", "precision": 10, "scale": 3, "nullable": true},
+                {"name": "PLACED_AT", "type": "timestamp_ltz", "scale": 9, "nullable": true},
+                {"name": "DAY", "type": "date", "nullable": true},
+                {"name": "PAYLOAD", "type": "variant", "nullable": true},
+                {"name": "Note", "type": "text", "nullable": true},
+                {"name": "IGNORED", "type": "text", "nullable": true}
+            ]),
+            serde_json::json!([
+                [
+                    "1",
+                    "-0.05",
+                    "0.125",
+                    "1700000000.500000000",
+                    "19675",
+                    "{\"a\": [1]}",
+                    null,
+                    "x"
+                ],
+                ["2", "10.00", null, "0.000000000", "0", "[]", "hi", null]
+            ]),
+        );
+
+        let rows: Vec<Order> = result.deserialize_rows().unwrap();
+        assert_eq!(
+            rows[0],
+            Order {
+                id: 1,
+                price: Decimal::new(-5, 2),
+                ratio: Some(0.125),
+                placed_at: DateTime::from_timestamp_millis(1_700_000_000_500).unwrap(),
+                day: NaiveDate::from_ymd_opt(2023, 11, 14).unwrap(),
+                payload: serde_json::json!({"a": [1]}),
+                note: None,
+            }
+        );
+        assert_eq!(rows[1].note.as_deref(), Some("hi"));
+
+        let tuples: Vec<(i64, String)> = json_result(
+            serde_json::json!([
+                {"name": "ID", "type": "fixed", "precision": 18, "scale": 0, "nullable": false},
+                {"name": "NAME", "type": "text", "nullable": true}
+            ]),
+            serde_json::json!([["7", "seven"]]),
+        )
+        .deserialize_rows()
+        .unwrap();
+        assert_eq!(tuples, vec![(7, "seven".to_string())]);
+
+        let maps: Vec<HashMap<String, Value>> = result.deserialize_rows().unwrap();
+        assert_eq!(maps[1]["Note"], "hi");
+    }
+
+    #[test]
+    fn test_deserialize_integers_from_number_38() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            id: i64,
+            parent: Option<i64>,
+            count: u64,
+        }
+
+        let result = json_result(
+            serde_json::json!([
+                {"name": "ID", "type": "fixed", "precision": 38, "scale": 0, "nullable": false},
+                {"name": "PARENT", "type": "fixed", "precision": 38, "scale": 0, "nullable": true},
+                {"name": "COUNT", "type": "fixed", "precision": 38, "scale": 0, "nullable": false}
+            ]),
+            serde_json::json!([["7", "-3", "18446744073709551615"], ["8", null, "0"]]),
+        );
+
+        let rows: Vec<Row> = result.deserialize_rows().unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    id: 7,
+                    parent: Some(-3),
+                    count: u64::MAX,
+                },
+                Row {
+                    id: 8,
+                    parent: None,
+                    count: 0,
+                },
+            ]
+        );
+
+        let err = result.deserialize_rows::<(i64, i64, i64)>().unwrap_err();
+        let SnowflakeApiError::RowDeserializationError { row, column, .. } = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!((*row, column.as_deref()), (0, Some("COUNT")));
+        assert!(err.to_string().contains("out of range for i64"), "{err}");
+    }
+
+    #[test]
+    fn test_deserialize_error_names_row_and_column() {
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Row {
+            id: i64,
+            name: u8,
+        }
+
+        let result = json_result(
+            serde_json::json!([
+                {"name": "ID", "type": "fixed", "precision": 18, "scale": 0, "nullable": false},
+                {"name": "NAME", "type": "text", "nullable": true}
+            ]),
+            serde_json::json!([["1", "1"], ["2", "two"]]),
+        );
+
+        let err = result.deserialize_rows::<Row>().unwrap_err();
+        let SnowflakeApiError::RowDeserializationError { row, column, .. } = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(*row, 0);
+        assert_eq!(column.as_deref(), Some("NAME"));
+        assert!(err.to_string().contains("column `NAME`"));
+    }
+}
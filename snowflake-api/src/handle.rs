@@ -0,0 +1,187 @@
+use uuid::Uuid;
+
+use crate::responses::{BaseRestResponse, ExecRestResponse};
+use crate::{into_resp_type, QueryStatus, SnowflakeApi, SnowflakeApiError};
+
+/// Handle to the query executed asynchronously, see [`SnowflakeApi::submit`].
+/// Only the query id is needed to collect the result, so it could be passed to another process
+/// and turned back into the handle with [`SnowflakeApi::query_handle`].
+/// Cancelling needs the request id as well, see [`QueryHandle::with_request_id`].
+pub struct QueryHandle<'a> {
+    api: &'a SnowflakeApi,
+    query_id: String,
+    request_id: Option<Uuid>,
+}
+
+impl<'a> QueryHandle<'a> {
+    pub(crate) fn new(api: &'a SnowflakeApi, query_id: String) -> Self {
+        Self {
+            api,
+            query_id,
+            request_id: None,
+        }
+    }
+
+    /// Id of the request the query was submitted with, so it could be cancelled by the restored handle
+    #[must_use]
+    pub fn with_request_id(mut self, request_id: Uuid) -> Self {
+        self.request_id = Some(request_id);
+        self
+    }
+
+    pub fn query_id(&self) -> &str {
+        &self.query_id
+    }
+
+    /// Id of the request the query was submitted with, unknown for the handle restored by the query id only
+    pub fn request_id(&self) -> Option<Uuid> {
+        self.request_id
+    }
+
+    /// Current status of the query, returns right away
+    pub async fn status(&self) -> Result<QueryStatus, SnowflakeApiError> {
+        self.api.query_status(&self.query_id).await
+    }
+
+    /// Waits for the query to finish, without downloading the result.
+    /// Errors if query has failed.
+    pub async fn wait(&self) -> Result<(), SnowflakeApiError> {
+        let resp = self.api.query_result(&self.query_id).await?;
+        self.api.wait_for_result(resp).await?;
+        Ok(())
+    }
+
+    /// Waits for the query to finish and downloads the result
+    pub async fn fetch(&self) -> Result<ExecRestResponse, SnowflakeApiError> {
+        let mut base_rest_res = self.api.fetch_result_raw(&self.query_id).await?;
+        base_rest_res.request_id = self.request_id;
+        Ok(into_resp_type!(
+            &base_rest_res,
+            self.api.to_query_result(base_rest_res.data)?
+        ))
+    }
+
+    /// Abort the query on the server.
+    /// Fails with [`SnowflakeApiError::UnknownRequestId`] if the request id isn't known.
+    pub async fn cancel(&self) -> Result<(), SnowflakeApiError> {
+        let Some(request_id) = self.request_id else {
+            return Err(SnowflakeApiError::UnknownRequestId {
+                query_id: self.query_id.clone(),
+            });
+        };
+        self.api.cancel(request_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::responses::QueryState;
+    use crate::test_utils::{
+        api, monitoring_response, mount_login, running_response, select_one_response,
+    };
+    use crate::SnowflakeApiError;
+
+    const QUERY_ID: &str = "01b2c3d4-0000-0001-0000-000000000001";
+
+    async fn mount_monitoring(server: &MockServer, states: &[&str]) {
+        for (i, state) in states.iter().enumerate() {
+            let mock = Mock::given(path(format!("/monitoring/queries/{QUERY_ID}")))
+                .respond_with(monitoring_response(QUERY_ID, state));
+            // last state is reported from then on
+            let mock = if i + 1 < states.len() {
+                mock.up_to_n_times(1)
+            } else {
+                mock
+            };
+            mock.mount(server).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_status_wait_and_fetch() {
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .and(body_partial_json(
+                json!({"sqlText": "SELECT 1", "asyncExec": true}),
+            ))
+            .respond_with(running_response(QUERY_ID))
+            .expect(1)
+            .mount(&server)
+            .await;
+        mount_monitoring(&server, &["QUEUED", "RUNNING", "SUCCESS"]).await;
+        Mock::given(path(format!("/queries/{QUERY_ID}/result")))
+            .respond_with(running_response(QUERY_ID))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("/queries/{QUERY_ID}/result")))
+            .respond_with(select_one_response(QUERY_ID))
+            .mount(&server)
+            .await;
+
+        let mut api = api(&server);
+        api.poll_policy.initial_delay = Duration::from_millis(1);
+        let handle = api.submit("SELECT 1").await.unwrap();
+        assert_eq!(handle.query_id(), QUERY_ID);
+
+        let mut states = vec![];
+        for _ in 0..3 {
+            states.push(handle.status().await.unwrap().state);
+        }
+        assert_eq!(
+            states,
+            vec![QueryState::Queued, QueryState::Running, QueryState::Success]
+        );
+
+        // result is polled until the query has finished
+        handle.wait().await.unwrap();
+        let polls = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.url.path() == format!("/queries/{QUERY_ID}/result"))
+            .count();
+        assert_eq!(polls, 3);
+
+        let result = handle.fetch().await.unwrap().data;
+        assert_eq!(result.rows().unwrap()[0].get::<i64>("ONE").unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_failed_query() {
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(path(format!("/queries/{QUERY_ID}/result")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": "000604",
+                "message": "SQL execution canceled",
+                "success": false,
+                "data": {
+                    "age": 0,
+                    "errorCode": "000604",
+                    "internalError": false,
+                    "queryId": QUERY_ID,
+                    "sqlState": "57014"
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let api = api(&server);
+        let err = api.query_handle(QUERY_ID).wait().await.unwrap_err();
+        assert!(matches!(
+            err,
+            SnowflakeApiError::ApiError { ref code, ref query_id, .. }
+                if code == "000604" && query_id == QUERY_ID
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use tokio::runtime::{Handle, TryCurrentError};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+use crate::session::Session;
+
+/// Background task keeping the session alive, stops when dropped or when the session is gone
+pub(crate) struct Heartbeat {
+    handle: JoinHandle<()>,
+}
+
+impl Heartbeat {
+    pub fn spawn(session: &Arc<Session>, interval: Duration) -> Result<Self, TryCurrentError> {
+        let runtime = Handle::try_current()?;
+        let session = Arc::downgrade(session);
+        let handle = runtime.spawn(run(session, interval));
+        Ok(Self { handle })
+    }
+}
+
+async fn run(session: Weak<Session>, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // first tick completes immediately, session has just been created
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let Some(session) = session.upgrade() else {
+            break;
+        };
+        if let Err(e) = session.heartbeat(period).await {
+            log::warn!("Session heartbeat failed: {e}");
+        }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{connection, login_response, session};
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn heartbeat_count(server: &MockServer) -> usize {
+        server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter(|r| r.url.path() == "/session/heartbeat")
+            .count()
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_stops_on_drop() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/session/v1/login-request"))
+            .respond_with(login_response("session"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/session/heartbeat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": null,
+                "message": null,
+                "success": true,
+                "data": null
+            })))
+            .mount(&server)
+            .await;
+
+        let session = Arc::new(session(connection(&server)).with_keep_alive(true));
+        session.get_token().await.unwrap();
+
+        let heartbeat = Heartbeat::spawn(&session, Duration::from_millis(50)).unwrap();
+        tokio::time::sleep(Duration::from_millis(180)).await;
+        drop(heartbeat);
+        let sent = heartbeat_count(&server).await;
+        assert!(sent >= 2, "expected heartbeats, got {sent}");
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(heartbeat_count(&server).await, sent);
+    }
+}
@@ -0,0 +1,473 @@
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BinaryArray, FixedSizeListArray, Float64Array, ListArray, StringArray,
+    Time64NanosecondArray, TimestampNanosecondArray,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::compute::{cast, cast_with_options, CastOptions};
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use serde_json::Value;
+
+use crate::responses::SnowflakeType;
+use crate::{schema, FieldSchema, JsonResult, SnowflakeApiError};
+
+const NANOS_IN_SECOND: i64 = 1_000_000_000;
+
+impl JsonResult {
+    /// Parse string cells of the JSON result into a typed batch, with the same schema
+    /// [`FieldSchema::to_arrow_field`] gives
+    pub fn to_record_batch(&self) -> Result<RecordBatch, ArrowError> {
+        let rows = self.value.as_array().map_or(&[][..], Vec::as_slice);
+        json_rows_to_batch(rows, &self.schema)
+    }
+}
+
+/// Rows are arrays of cells, ordered the same way as fields
+pub(crate) fn json_rows_to_batch(
+    rows: &[Value],
+    fields: &[FieldSchema],
+) -> Result<RecordBatch, ArrowError> {
+    let schema = schema::arrow_schema(fields);
+    let columns = fields
+        .iter()
+        .zip(schema.fields())
+        .enumerate()
+        .map(|(i, (field, arrow_field))| {
+            let cells = rows
+                .iter()
+                .map(|row| cell_to_str(row.get(i).unwrap_or(&Value::Null)))
+                .collect::<Vec<_>>();
+            parse_column(field, arrow_field.data_type(), &cells)
+                .map_err(|e| ArrowError::ParseError(format!("Column `{}`: {e}", field.name)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // row count has to be explicit for the results without columns
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    RecordBatch::try_new_with_options(schema, columns, &options)
+}
+
+/// Cells are strings, but be lenient to numbers and booleans as well
+fn cell_to_str(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn parse_column(
+    field: &FieldSchema,
+    data_type: &DataType,
+    cells: &[Option<String>],
+) -> Result<ArrayRef, ArrowError> {
+    let strings: StringArray = cells.iter().map(Option::as_deref).collect();
+    let strict = CastOptions {
+        safe: false,
+        ..CastOptions::default()
+    };
+
+    let array: ArrayRef = match field.type_ {
+        SnowflakeType::Fixed | SnowflakeType::Real | SnowflakeType::Boolean => {
+            cast_with_options(&strings, data_type, &strict)?
+        }
+        // days since epoch
+        SnowflakeType::Date => {
+            let days = cast_with_options(&strings, &DataType::Int32, &strict)?;
+            cast(&days, data_type)?
+        }
+        SnowflakeType::Time => Arc::new(
+            cells
+                .iter()
+                .map(|c| c.as_deref().map(parse_seconds).transpose())
+                .collect::<Result<Time64NanosecondArray, _>>()?,
+        ),
+        SnowflakeType::TimestampNtz | SnowflakeType::TimestampLtz | SnowflakeType::TimestampTz => {
+            Arc::new(
+                cells
+                    .iter()
+                    .map(|c| c.as_deref().map(parse_timestamp).transpose())
+                    .collect::<Result<TimestampNanosecondArray, _>>()?
+                    .with_data_type(data_type.clone()),
+            )
+        }
+        SnowflakeType::Binary => Arc::new(
+            cells
+                .iter()
+                .map(|c| c.as_deref().map(hex_decode).transpose())
+                .collect::<Result<BinaryArray, _>>()?,
+        ),
+        SnowflakeType::Vector => parse_vector(cells, data_type, &strict)?,
+        SnowflakeType::Text
+        | SnowflakeType::Variant
+        | SnowflakeType::Object
+        | SnowflakeType::Array
+        | SnowflakeType::Map
+        | SnowflakeType::Geography
+        | SnowflakeType::Geometry
+        | SnowflakeType::Decfloat
+        | SnowflakeType::Unknown(_) => Arc::new(strings),
+    };
+
+    Ok(array)
+}
+
+/// `VECTOR` cells are JSON arrays of numbers
+fn parse_vector(
+    cells: &[Option<String>],
+    data_type: &DataType,
+    options: &CastOptions,
+) -> Result<ArrayRef, ArrowError> {
+    let (item, dimension) = match data_type {
+        DataType::FixedSizeList(item, dimension) => (item, Some(*dimension)),
+        DataType::List(item) => (item, None),
+        other => {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "Unexpected vector type: {other}"
+            )))
+        }
+    };
+    let expected_len = dimension.and_then(|d| usize::try_from(d).ok());
+
+    let mut values = vec![];
+    let mut lengths = vec![];
+    for cell in cells {
+        let elements: Vec<f64> = match cell {
+            Some(cell) => serde_json::from_str(cell)
+                .map_err(|e| ArrowError::ParseError(format!("Invalid vector `{cell}`: {e}")))?,
+            // fixed size list has to have the placeholder values for nulls too
+            None => vec![0.0; expected_len.unwrap_or(0)],
+        };
+        if expected_len.is_some_and(|len| len != elements.len()) {
+            return Err(ArrowError::ParseError(format!(
+                "Vector has {} elements instead of {}",
+                elements.len(),
+                expected_len.unwrap_or(0)
+            )));
+        }
+        lengths.push(elements.len());
+        values.extend(elements);
+    }
+
+    let values = cast_with_options(&Float64Array::from(values), item.data_type(), options)?;
+    let nulls: NullBuffer = cells.iter().map(Option::is_some).collect();
+    Ok(match dimension {
+        Some(dimension) => Arc::new(FixedSizeListArray::try_new(
+            Arc::clone(item),
+            dimension,
+            values,
+            Some(nulls),
+        )?),
+        None => Arc::new(ListArray::try_new(
+            Arc::clone(item),
+            OffsetBuffer::from_lengths(lengths),
+            values,
+            Some(nulls),
+        )?),
+    })
+}
+
+/// `TIMESTAMP_TZ` has the timezone offset after the space, in minutes shifted by 1440.
+/// Epoch is in UTC already, so it's not needed.
+fn parse_timestamp(value: &str) -> Result<i64, ArrowError> {
+    let epoch = value
+        .split_once(' ')
+        .map_or(value, |(epoch, _offset)| epoch);
+    parse_seconds(epoch)
+}
+
+/// Seconds with the fraction, eg `1700000000.123000000` or `-1.500000000`, into nanoseconds
+fn parse_seconds(value: &str) -> Result<i64, ArrowError> {
+    let invalid = || ArrowError::ParseError(format!("Invalid seconds value: `{value}`"));
+
+    let (seconds, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let seconds: i64 = seconds.parse().map_err(|_| invalid())?;
+    let fraction: i64 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{fraction:0<9}").parse().map_err(|_| invalid())?
+    };
+    // fraction has the same sign as the whole value
+    let fraction = if value.starts_with('-') {
+        -fraction
+    } else {
+        fraction
+    };
+
+    seconds
+        .checked_mul(NANOS_IN_SECOND)
+        .and_then(|nanos| nanos.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+fn hex_decode(value: &str) -> Result<Vec<u8>, ArrowError> {
+    let invalid = || ArrowError::ParseError(format!("Invalid hex value: `{value}`"));
+    if value.len() % 2 == 1 {
+        return Err(invalid());
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            value
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+/// Parse the JSON result chunk. Chunks are comma-separated row arrays without the enclosing brackets,
+/// eg `["a", "1"], ["b", "2"]`. Rows are parsed one by one right from the bytes.
+pub(crate) fn parse_json_chunk(bytes: &[u8]) -> Result<Vec<Value>, SnowflakeApiError> {
+    let invalid = |pos: usize, reason: &str| {
+        SnowflakeApiError::InvalidJsonChunk(format!("{reason} at byte {pos}"))
+    };
+
+    let mut rows = vec![];
+    let mut pos = skip_whitespace(bytes, 0);
+    while pos < bytes.len() {
+        let mut values = serde_json::Deserializer::from_slice(&bytes[pos..]).into_iter::<Value>();
+        let row = match values.next() {
+            Some(Ok(row @ Value::Array(_))) => row,
+            Some(Ok(_)) => return Err(invalid(pos, "expected row array")),
+            Some(Err(e)) => return Err(invalid(pos, &e.to_string())),
+            None => return Err(invalid(pos, "expected row")),
+        };
+        rows.push(row);
+        pos = skip_whitespace(bytes, pos + values.byte_offset());
+
+        match bytes.get(pos) {
+            None => break,
+            Some(b',') => {
+                pos = skip_whitespace(bytes, pos + 1);
+                if pos == bytes.len() {
+                    return Err(invalid(pos, "expected row after comma"));
+                }
+            }
+            Some(_) => return Err(invalid(pos, "expected comma between rows")),
+        }
+    }
+
+    Ok(rows)
+}
+
+fn skip_whitespace(bytes: &[u8], pos: usize) -> usize {
+    bytes[pos..]
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .map_or(bytes.len(), |offset| pos + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{
+        Date32Type, Decimal128Type, Field, Float64Type, Int64Type, Time64NanosecondType,
+        TimestampNanosecondType,
+    };
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::QueryResult;
+
+    #[test]
+    fn test_newer_types() {
+        let result = JsonResult::from_rowtype(
+            serde_json::json!([
+                {"name": "EMBEDDING", "type": "vector", "vectorDimension": 3, "nullable": true,
+                 "fields": [{"type": "fixed", "precision": 38, "scale": 0, "nullable": false}]},
+                {"name": "AREA", "type": "geography", "nullable": true},
+                {"name": "AMOUNT", "type": "decfloat", "nullable": true},
+                {"name": "ATTRS", "type": "map", "nullable": true},
+                {"name": "SPAN", "type": "interval_year_month", "nullable": true}
+            ]),
+            serde_json::json!([
+                [
+                    "[1,2,3]",
+                    "{\"type\": \"Point\", \"coordinates\": [13.4, 52.5]}",
+                    "1.5E-3",
+                    "{\"k\": \"v\"}",
+                    "1-2"
+                ],
+                [null, null, null, null, null]
+            ]),
+        );
+        assert_eq!(
+            result.schema[4].type_,
+            SnowflakeType::Unknown("INTERVAL_YEAR_MONTH".to_string())
+        );
+
+        let batch = result.to_record_batch().unwrap();
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &DataType::FixedSizeList(Arc::new(Field::new_list_field(DataType::Int32, false)), 3)
+        );
+        assert!(batch.column(0).is_null(1));
+
+        let rows = QueryResult::Json(result).rows().unwrap();
+        assert_eq!(rows[0].get::<Vec<i32>>("EMBEDDING").unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            rows[0].get_path("AREA:type").unwrap(),
+            serde_json::json!("Point")
+        );
+        assert_eq!(
+            rows[0].get::<rust_decimal::Decimal>("AMOUNT").unwrap(),
+            rust_decimal::Decimal::new(15, 4)
+        );
+        assert_eq!(rows[0].get_path("ATTRS:k").unwrap(), serde_json::json!("v"));
+        assert_eq!(rows[0].get::<String>("SPAN").unwrap(), "1-2");
+        assert_eq!(rows[1].get::<Option<Vec<i32>>>("EMBEDDING").unwrap(), None);
+    }
+
+    #[test]
+    fn test_json_result_to_record_batch() {
+        let result = JsonResult::from_rowtype(
+            serde_json::json!([
+                {"name": "ID", "type": "fixed", "precision": 18, "scale": 0, "nullable": false},
+                {"name": "PRICE", "type": "fixed", "precision": 10, "scale": 2, "nullable": true},
+                {"name": "RATIO", "type": "real", "nullable": true},
+                {"name": "DAY", "type": "date", "nullable": true},
+                {"name": "AT", "type": "time", "scale": 9, "nullable": true},
+                {"name": "NTZ", "type": "timestamp_ntz", "scale": 9, "nullable": true},
+                {"name": "TZ", "type": "timestamp_tz", "scale": 9, "nullable": true},
+                {"name": "FLAG", "type": "boolean", "nullable": true},
+                {"name": "DATA", "type": "binary", "nullable": true},
+                {"name": "DOC", "type": "variant", "nullable": true}
+            ]),
+            serde_json::json!([
+                [
+                    "1",
+                    "123.45",
+                    "0.5",
+                    "19675",
+                    "45296.789000000",
+                    "1700000000.123000000",
+                    "1700000000.000000001 1500",
+                    "1",
+                    "CAFE",
+                    "{\"a\": 1}"
+                ],
+                [
+                    "2",
+                    null,
+                    null,
+                    null,
+                    null,
+                    "-1.500000000",
+                    null,
+                    "false",
+                    null,
+                    null
+                ]
+            ]),
+        );
+
+        let batch = result.to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(1), 2);
+        assert_eq!(
+            batch
+                .column(1)
+                .as_primitive::<Decimal128Type>()
+                .value_as_string(0),
+            "123.45"
+        );
+        assert!(batch.column(1).is_null(1));
+        assert!(
+            (batch.column(2).as_primitive::<Float64Type>().value(0) - 0.5).abs() < f64::EPSILON
+        );
+        assert_eq!(
+            batch.column(3).as_primitive::<Date32Type>().value(0),
+            19_675
+        );
+        assert_eq!(
+            batch
+                .column(4)
+                .as_primitive::<Time64NanosecondType>()
+                .value(0),
+            45_296_789_000_000
+        );
+        let ntz = batch.column(5).as_primitive::<TimestampNanosecondType>();
+        assert_eq!(ntz.value(0), 1_700_000_000_123_000_000);
+        assert_eq!(ntz.value(1), -1_500_000_000);
+        assert_eq!(
+            batch
+                .column(6)
+                .as_primitive::<TimestampNanosecondType>()
+                .value(0),
+            1_700_000_000_000_000_001
+        );
+        assert!(batch.column(7).as_boolean().value(0));
+        assert!(!batch.column(7).as_boolean().value(1));
+        assert_eq!(batch.column(8).as_binary::<i32>().value(0), [0xCA, 0xFE]);
+        assert_eq!(batch.column(9).as_string::<i32>().value(0), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn test_invalid_cell_names_column() {
+        let result = JsonResult::from_rowtype(
+            serde_json::json!([
+                {"name": "ID", "type": "fixed", "precision": 18, "scale": 0, "nullable": false}
+            ]),
+            serde_json::json!([["nope"]]),
+        );
+
+        let err = result.to_record_batch().unwrap_err();
+        assert!(err.to_string().contains("`ID`"));
+    }
+
+    #[test]
+    fn test_parse_json_chunk() {
+        let chunk = br#"["a", "1"], ["], [", null],
+["\u00e9", "2"] "#;
+        let rows = parse_json_chunk(chunk).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                serde_json::json!(["a", "1"]),
+                serde_json::json!(["], [", null]),
+                serde_json::json!(["\u{e9}", "2"]),
+            ]
+        );
+
+        assert!(parse_json_chunk(b"").unwrap().is_empty());
+        assert!(parse_json_chunk(br#"["a"] ["b"]"#).is_err());
+        assert!(parse_json_chunk(br#"["a"],"#).is_err());
+        assert!(parse_json_chunk(br#"{"a": 1}"#).is_err());
+        assert!(parse_json_chunk(br#"["a", "#).is_err());
+    }
+
+    fn row_strategy() -> impl Strategy<Value = Vec<Option<String>>> {
+        prop::collection::vec(prop::option::of(any::<String>()), 0..5)
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_json_chunk_roundtrip(
+            rows in prop::collection::vec(row_strategy(), 0..20),
+            separator in prop::sample::select(vec![",", ", ", ",\n", " ,\r\n "]),
+        ) {
+            let chunk = rows
+                .iter()
+                .map(|row| serde_json::to_string(row).unwrap())
+                .collect::<Vec<_>>()
+                .join(separator);
+            let expected: Vec<Value> = rows.iter().map(|row| serde_json::json!(row)).collect();
+
+            prop_assert_eq!(parse_json_chunk(chunk.as_bytes()).unwrap(), expected);
+        }
+
+        #[test]
+        fn test_parse_json_chunk_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+            let _ = parse_json_chunk(&bytes);
+        }
+    }
+}
@@ -14,9 +14,12 @@ clippy::missing_panics_doc
 )]
 
 use std::fmt::{Display, Formatter};
+use std::future::Future;
 use std::io::{self};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use arrow::datatypes::SchemaRef;
 use arrow::error::ArrowError;
 use arrow::ipc::reader::StreamReader;
 use arrow::record_batch::RecordBatch;
@@ -27,24 +30,58 @@ use regex::Regex;
 use reqwest_middleware::ClientWithMiddleware;
 use serde_json::Value;
 use thiserror::Error;
+use uuid::Uuid;
 
-use responses::{ExecResponse, ExecRestResponse, ProcessedRestResponse, QueryContext};
-use session::{AuthError, Session};
+use heartbeat::Heartbeat;
+use responses::{
+    ExecResponse, ExecRestResponse, ProcessedRestResponse, QueryContext, QueryExecResponse,
+    QueryExecResponseData,
+};
+pub use session::OAuthTokenRefresh;
+use session::{AuthError, AuthParts, Session};
 
 use crate::connection::QueryType;
 use crate::connection::{Connection, ConnectionError};
-use crate::requests::{EmptyRequest, ExecRequest};
+use crate::requests::{Bindings, EmptyRequest, ExecRequest};
 use crate::responses::{BaseRestResponse, ExecResponseRowType, SnowflakeType};
 use crate::session::AuthError::MissingEnvArgument;
 
+pub use bindings::{BindParams, BindValue};
+pub use cell::CellValue;
+pub use handle::QueryHandle;
+pub use normalize::normalize_batch;
+pub use options::{ExecOptions, ResultFormat};
+pub use row::{ColumnIndex, FromSnowflake, Row};
+pub use statement::{DmlResult, StatementType};
+pub use status::{PollPolicy, ProgressCallback, QueryStatus};
+pub use variant::{flatten, PathSegment, VariantPath};
+
+mod bindings;
+mod browser;
+mod cancel;
+mod cell;
 pub mod connection;
+mod de;
+mod handle;
+mod heartbeat;
+mod json;
+mod normalize;
+mod options;
 #[cfg(feature = "polars")]
 mod polars;
 mod put;
 mod requests;
 pub mod responses;
+mod row;
+mod schema;
 mod session;
+mod statement;
+mod status;
+mod stream;
+#[cfg(test)]
+mod test_utils;
 mod utils;
+mod variant;
 
 #[derive(Error, Debug)]
 pub enum SnowflakeApiError {
@@ -78,6 +115,9 @@ pub enum SnowflakeApiError {
     #[error(transparent)]
     TokioTaskJoinError(#[from] tokio::task::JoinError),
 
+    #[error(transparent)]
+    TokioRuntimeError(#[from] tokio::runtime::TryCurrentError),
+
     #[error(
         "Snowflake API error. Code: `{code:?}`. Message: `{message:?}`. QueryId: `{query_id:?}`"
     )]
@@ -107,12 +147,49 @@ pub enum SnowflakeApiError {
 
     #[error(transparent)]
     GlobError(#[from] glob::GlobError),
+
+    #[error("Invalid bind parameters: {0}")]
+    InvalidBindings(String),
+
+    #[error("Invalid variant path {0}")]
+    InvalidVariantPath(String),
+
+    #[error("Malformed JSON result chunk: {0}")]
+    InvalidJsonChunk(String),
+
+    #[error(
+        "Failed to deserialize row {row}{}: {message}",
+        .column.as_ref().map(|c| format!(", column `{c}`")).unwrap_or_default()
+    )]
+    RowDeserializationError {
+        row: usize,
+        column: Option<String>,
+        message: String,
+    },
+
+    #[error("No such column: `{0}`")]
+    ColumnNotFound(String),
+
+    #[error("Column `{column}`: {message}")]
+    CellConversionError { column: String, message: String },
+
+    #[error("Query `{query_id}` didn't finish before the polling deadline")]
+    QueryTimeout { query_id: String },
+
+    #[error(
+        "Query `{query_id}` can't be cancelled without the id of the request it was sent with"
+    )]
+    UnknownRequestId { query_id: String },
 }
 
+/// Result of the statement which doesn't produce a result set, eg PUT
 #[derive(Debug)]
 pub struct EmptyJsonResult {
+    /// Always `None`: statement without result set has no schema,
+    /// and result set without rows is returned as [`QueryResult::Arrow`] with an empty batch
     pub schema: Option<Vec<FieldSchema>>,
     pub query_id: String,
+    pub statement_type_id: Option<i64>,
     pub send_result_time: usize,
     pub query_context: QueryContext,
 }
@@ -126,6 +203,7 @@ pub struct JsonResult {
     /// Field ordering matches the array ordering
     pub schema: Vec<FieldSchema>,
     pub query_id: String,
+    pub statement_type_id: Option<i64>,
     pub send_result_time: usize,
     pub query_context: QueryContext,
 }
@@ -136,10 +214,29 @@ impl Display for JsonResult {
     }
 }
 
+#[cfg(test)]
+impl JsonResult {
+    /// Result with the `rowtype` and `rowset` as they are in the query response
+    pub(crate) fn from_rowtype(rowtype: Value, rowset: Value) -> Self {
+        let rowtype: Vec<ExecResponseRowType> = serde_json::from_value(rowtype).unwrap();
+        Self {
+            value: rowset,
+            schema: rowtype.into_iter().map(Into::into).collect(),
+            query_id: String::new(),
+            statement_type_id: None,
+            send_result_time: 0,
+            query_context: QueryContext { entries: vec![] },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct BytesResult {
     pub chunks: Vec<Bytes>,
+    /// Result schema, used to build an empty batch when there are no chunks
+    pub schema: Option<SchemaRef>,
     pub query_id: String,
+    pub statement_type_id: Option<i64>,
     pub send_result_time: usize,
     pub query_context: QueryContext,
 }
@@ -152,7 +249,15 @@ pub struct FieldSchema {
     pub type_: SnowflakeType,
     pub scale: Option<i64>,
     pub precision: Option<i64>,
+    /// Max length in characters for text types
+    pub length: Option<i64>,
+    /// Max length in bytes for text and binary types
+    pub byte_length: Option<i64>,
     pub nullable: bool,
+    /// Number of elements of `VECTOR`
+    pub vector_dimension: Option<i64>,
+    /// Type of `VECTOR` elements, `FIXED` or `REAL`
+    pub element_type: Option<SnowflakeType>,
 }
 
 impl From<ExecResponseRowType> for FieldSchema {
@@ -162,7 +267,11 @@ impl From<ExecResponseRowType> for FieldSchema {
             type_: value.type_,
             scale: value.scale,
             precision: value.precision,
+            length: value.length,
+            byte_length: value.byte_length,
             nullable: value.nullable,
+            vector_dimension: value.vector_dimension,
+            element_type: value.fields.into_iter().next().map(|f| f.type_),
         }
     }
 }
@@ -171,6 +280,7 @@ impl From<ExecResponseRowType> for FieldSchema {
 pub struct ArrowResult {
     pub batches: Vec<RecordBatch>,
     pub query_id: String,
+    pub statement_type_id: Option<i64>,
     pub send_result_time: usize,
     pub query_context: QueryContext,
 }
@@ -201,10 +311,16 @@ impl RawQueryResult {
     pub fn deserialize_arrow(self) -> Result<QueryResult, ArrowError> {
         match self {
             RawQueryResult::Bytes(bytes_result) => Self::flat_bytes_to_batches(bytes_result.chunks)
-                .map(|batches| {
+                .map(|mut batches| {
+                    if batches.is_empty() {
+                        if let Some(schema) = bytes_result.schema {
+                            batches.push(RecordBatch::new_empty(schema));
+                        }
+                    }
                     QueryResult::Arrow(ArrowResult {
                         batches,
                         query_id: bytes_result.query_id,
+                        statement_type_id: bytes_result.statement_type_id,
                         send_result_time: bytes_result.send_result_time,
                         query_context: bytes_result.query_context,
                     })
@@ -245,9 +361,11 @@ impl AuthArgs {
             Ok(AuthType::Password(PasswordArgs { password }))
         } else if let Ok(private_key_pem) = std::env::var("SNOWFLAKE_PRIVATE_KEY") {
             Ok(AuthType::Certificate(CertificateArgs { private_key_pem }))
+        } else if let Ok(token) = std::env::var("SNOWFLAKE_TOKEN") {
+            Ok(AuthType::OAuth(OAuthArgs::new(token)))
         } else {
             Err(MissingEnvArgument(
-                "SNOWFLAKE_PASSWORD or SNOWFLAKE_PRIVATE_KEY".to_owned(),
+                "SNOWFLAKE_PASSWORD, SNOWFLAKE_PRIVATE_KEY or SNOWFLAKE_TOKEN".to_owned(),
             ))
         };
 
@@ -268,6 +386,9 @@ impl AuthArgs {
 pub enum AuthType {
     Password(PasswordArgs),
     Certificate(CertificateArgs),
+    OAuth(OAuthArgs),
+    /// SSO through the identity provider in the web browser
+    ExternalBrowser,
 }
 
 pub struct PasswordArgs {
@@ -278,15 +399,75 @@ pub struct CertificateArgs {
     pub private_key_pem: String,
 }
 
+pub struct OAuthArgs {
+    /// Access token issued by the identity provider
+    pub token: String,
+    /// Called for a new access token when the server reports the current one has expired
+    pub refresh: Option<OAuthTokenRefresh>,
+}
+
+impl OAuthArgs {
+    pub fn new(token: String) -> Self {
+        Self {
+            token,
+            refresh: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_refresh<F, Fut>(mut self, refresh: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, Box<dyn std::error::Error + Send + Sync>>>
+            + Send
+            + 'static,
+    {
+        self.refresh = Some(Arc::new(move || Box::pin(refresh())));
+        self
+    }
+}
+
+/// Number of array-bound values above which they are uploaded to the stage,
+/// same as `CLIENT_STAGE_ARRAY_BINDING_THRESHOLD` default in the official drivers
+pub const DEFAULT_STAGE_BINDING_THRESHOLD: usize = 65_280;
+
+/// Number of result chunks downloaded ahead of the consumer when streaming the result
+pub const DEFAULT_CHUNK_PREFETCH: usize = 4;
+
+/// Statement parameter allowing several statements in a single request
+const MULTI_STATEMENT_COUNT: &str = "MULTI_STATEMENT_COUNT";
+
+/// Temporary stage used to upload large array bindings
+const BIND_STAGE_NAME: &str = "SYSTEM$BIND";
+
 #[must_use]
 pub struct SnowflakeApiBuilder {
     pub auth: AuthArgs,
     client: Option<ClientWithMiddleware>,
+    stage_binding_threshold: usize,
+    cancel_on_drop: bool,
+    chunk_prefetch: usize,
+    progress_callback: Option<ProgressCallback>,
+    poll_policy: PollPolicy,
+    normalize_arrow: bool,
+    json_as_arrow: bool,
+    keep_alive: Option<Duration>,
 }
 
 impl SnowflakeApiBuilder {
     pub fn new(auth: AuthArgs) -> Self {
-        Self { auth, client: None }
+        Self {
+            auth,
+            client: None,
+            stage_binding_threshold: DEFAULT_STAGE_BINDING_THRESHOLD,
+            cancel_on_drop: false,
+            chunk_prefetch: DEFAULT_CHUNK_PREFETCH,
+            progress_callback: None,
+            poll_policy: PollPolicy::default(),
+            normalize_arrow: false,
+            json_as_arrow: false,
+            keep_alive: None,
+        }
     }
 
     pub fn with_client(mut self, client: ClientWithMiddleware) -> Self {
@@ -294,6 +475,68 @@ impl SnowflakeApiBuilder {
         self
     }
 
+    /// Array bindings with more values than `threshold` are uploaded to the temporary stage
+    /// instead of being sent in the request body
+    pub fn with_stage_binding_threshold(mut self, threshold: usize) -> Self {
+        self.stage_binding_threshold = threshold;
+        self
+    }
+
+    /// Abort the statement on the server when the future executing it is dropped before completion,
+    /// eg because of the timeout. Requires tokio runtime to send the abort request.
+    /// Applies to `exec*` methods except for PUT, to streams until the statement has finished,
+    /// to `exec_multi` and `describe`.
+    /// Queries started with [`SnowflakeApi::submit`] are never aborted on drop.
+    pub fn with_cancel_on_drop(mut self, enabled: bool) -> Self {
+        self.cancel_on_drop = enabled;
+        self
+    }
+
+    /// Maximum number of result chunks downloaded in advance by the streaming API,
+    /// bounds memory used by the not yet consumed part of the result
+    pub fn with_chunk_prefetch(mut self, chunks: usize) -> Self {
+        self.chunk_prefetch = chunks.max(1);
+        self
+    }
+
+    /// Called with the current query status every time the long-running query is polled,
+    /// eg to report queueing or warehouse resume
+    pub fn with_progress_callback(
+        mut self,
+        callback: impl Fn(&QueryStatus) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Delays between polls of the long-running query result and how long to wait for it
+    pub fn with_poll_policy(mut self, policy: PollPolicy) -> Self {
+        self.poll_policy = policy;
+        self
+    }
+
+    /// Convert Snowflake-specific encodings in the Arrow result into standard Arrow types,
+    /// eg scaled integers into decimals and timestamp structs into timestamps, see [`normalize_batch`]
+    pub fn with_arrow_normalization(mut self, enabled: bool) -> Self {
+        self.normalize_arrow = enabled;
+        self
+    }
+
+    /// Convert JSON results, eg of `SHOW` statements, into Arrow,
+    /// so that query result is never [`QueryResult::Json`], see [`JsonResult::to_record_batch`]
+    pub fn with_json_as_arrow(mut self, enabled: bool) -> Self {
+        self.json_as_arrow = enabled;
+        self
+    }
+
+    /// Keep the session alive while the API is in use (`CLIENT_SESSION_KEEP_ALIVE`),
+    /// heartbeat is sent every `interval` and the session token is renewed before it expires.
+    /// Snowflake drivers default to one hour. Requires tokio runtime when the API is built.
+    pub fn with_session_keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval.max(Duration::from_secs(1)));
+        self
+    }
+
     pub fn build(self) -> Result<SnowflakeApi, SnowflakeApiError> {
         let connection = match self.client {
             Some(client) => Arc::new(Connection::new_with_middware(client)),
@@ -321,23 +564,66 @@ impl SnowflakeApiBuilder {
                 self.auth.role.as_deref(),
                 &args.private_key_pem,
             ),
-        };
+            AuthType::OAuth(args) => {
+                let session = Session::oauth_auth(
+                    Arc::clone(&connection),
+                    &self.auth.account_identifier,
+                    self.auth.warehouse.as_deref(),
+                    self.auth.database.as_deref(),
+                    self.auth.schema.as_deref(),
+                    &self.auth.username,
+                    self.auth.role.as_deref(),
+                    &args.token,
+                );
+                match args.refresh {
+                    Some(refresh) => session.with_oauth_refresh(refresh),
+                    None => session,
+                }
+            }
+            AuthType::ExternalBrowser => Session::external_browser_auth(
+                Arc::clone(&connection),
+                &self.auth.account_identifier,
+                self.auth.warehouse.as_deref(),
+                self.auth.database.as_deref(),
+                self.auth.schema.as_deref(),
+                &self.auth.username,
+                self.auth.role.as_deref(),
+            ),
+        }
+        .with_keep_alive(self.keep_alive.is_some());
 
         let account_identifier = self.auth.account_identifier.to_uppercase();
 
-        Ok(SnowflakeApi::new(
-            Arc::clone(&connection),
-            session,
-            account_identifier,
-        ))
+        let mut api = SnowflakeApi::new(Arc::clone(&connection), session, account_identifier);
+        api.stage_binding_threshold = self.stage_binding_threshold;
+        api.cancel_on_drop = self.cancel_on_drop;
+        api.chunk_prefetch = self.chunk_prefetch;
+        api.progress_callback = self.progress_callback;
+        api.poll_policy = self.poll_policy;
+        api.normalize_arrow = self.normalize_arrow;
+        api.json_as_arrow = self.json_as_arrow;
+        api.heartbeat = self
+            .keep_alive
+            .map(|interval| Heartbeat::spawn(&api.session, interval))
+            .transpose()?;
+
+        Ok(api)
     }
 }
 
 /// Snowflake API, keeps connection pool and manages session for you
 pub struct SnowflakeApi {
     connection: Arc<Connection>,
-    session: Session,
+    session: Arc<Session>,
     account_identifier: String,
+    stage_binding_threshold: usize,
+    cancel_on_drop: bool,
+    chunk_prefetch: usize,
+    progress_callback: Option<ProgressCallback>,
+    poll_policy: PollPolicy,
+    normalize_arrow: bool,
+    json_as_arrow: bool,
+    heartbeat: Option<Heartbeat>,
 }
 
 impl SnowflakeApi {
@@ -345,8 +631,16 @@ impl SnowflakeApi {
     pub fn new(connection: Arc<Connection>, session: Session, account_identifier: String) -> Self {
         Self {
             connection,
-            session,
+            session: Arc::new(session),
             account_identifier,
+            stage_binding_threshold: DEFAULT_STAGE_BINDING_THRESHOLD,
+            cancel_on_drop: false,
+            chunk_prefetch: DEFAULT_CHUNK_PREFETCH,
+            progress_callback: None,
+            poll_policy: PollPolicy::default(),
+            normalize_arrow: false,
+            json_as_arrow: false,
+            heartbeat: None,
         }
     }
     /// Initialize object with password auth. Authentication happens on the first request.
@@ -411,25 +705,94 @@ impl SnowflakeApi {
         ))
     }
 
+    /// Initialize object with OAuth access token auth. Authentication happens on the first request.
+    pub fn with_oauth_auth(
+        account_identifier: &str,
+        warehouse: Option<&str>,
+        database: Option<&str>,
+        schema: Option<&str>,
+        username: &str,
+        role: Option<&str>,
+        token: &str,
+    ) -> Result<Self, SnowflakeApiError> {
+        let connection = Arc::new(Connection::new()?);
+
+        let session = Session::oauth_auth(
+            Arc::clone(&connection),
+            account_identifier,
+            warehouse,
+            database,
+            schema,
+            username,
+            role,
+            token,
+        );
+
+        let account_identifier = account_identifier.to_uppercase();
+        Ok(Self::new(
+            Arc::clone(&connection),
+            session,
+            account_identifier,
+        ))
+    }
+
+    /// Initialize object with external browser SSO auth. Authentication happens on the first request,
+    /// it opens the browser and waits for the user to log in with the identity provider.
+    pub fn with_external_browser_auth(
+        account_identifier: &str,
+        warehouse: Option<&str>,
+        database: Option<&str>,
+        schema: Option<&str>,
+        username: &str,
+        role: Option<&str>,
+    ) -> Result<Self, SnowflakeApiError> {
+        let connection = Arc::new(Connection::new()?);
+
+        let session = Session::external_browser_auth(
+            Arc::clone(&connection),
+            account_identifier,
+            warehouse,
+            database,
+            schema,
+            username,
+            role,
+        );
+
+        let account_identifier = account_identifier.to_uppercase();
+        Ok(Self::new(
+            Arc::clone(&connection),
+            session,
+            account_identifier,
+        ))
+    }
+
     pub fn from_env() -> Result<Self, SnowflakeApiError> {
         SnowflakeApiBuilder::new(AuthArgs::from_env()?).build()
     }
 
     /// Closes the current session, this is necessary to clean up temporary objects (tables, functions, etc)
     /// which are Snowflake session dependent.
-    /// If another request is made the new session will be initiated.
+    /// If another request is made the new session will be initiated, without the session keep-alive.
     pub async fn close_session(&mut self) -> Result<(), SnowflakeApiError> {
+        self.heartbeat = None;
         self.session.close().await?;
         Ok(())
     }
 
+    /// Closes the session and waits for the server to confirm it.
+    /// Otherwise the session is closed in the background when the API is dropped,
+    /// which may not complete if the runtime is shutting down, and isn't attempted outside of it.
+    pub async fn shutdown(mut self) -> Result<(), SnowflakeApiError> {
+        self.close_session().await
+    }
+
     /// Execute a single query against API.
     /// If statement is PUT, then file will be uploaded to the Snowflake-managed storage
     pub async fn exec(&self, sql: &str) -> Result<ExecRestResponse, SnowflakeApiError> {
         let base_rest_res = self.exec_raw(sql).await?;
         Ok(into_resp_type!(
             &base_rest_res,
-            base_rest_res.data.deserialize_arrow()?
+            self.to_query_result(base_rest_res.data)?
         ))
     }
 
@@ -437,35 +800,192 @@ impl SnowflakeApi {
     /// If statement is PUT, then file will be uploaded to the Snowflake-managed storage
     /// Returns raw bytes in the Arrow response
     pub async fn exec_raw(&self, sql: &str) -> Result<ProcessedRestResponse, SnowflakeApiError> {
+        self.exec_raw_with_params(sql, BindParams::default()).await
+    }
+
+    /// Execute a single query with values bound to its placeholders,
+    /// eg `SELECT * FROM t WHERE id = ?` or `... WHERE id = :1`.
+    /// Values are sent separately from the SQL text, so they don't need escaping.
+    pub async fn exec_with_params(
+        &self,
+        sql: &str,
+        params: impl Into<BindParams>,
+    ) -> Result<ExecRestResponse, SnowflakeApiError> {
+        let base_rest_res = self.exec_raw_with_params(sql, params).await?;
+        Ok(into_resp_type!(
+            &base_rest_res,
+            self.to_query_result(base_rest_res.data)?
+        ))
+    }
+
+    /// Execute a single query with per-statement options, eg query tag or timeout.
+    /// Options apply to this statement only and don't change the session.
+    pub async fn exec_with(
+        &self,
+        sql: &str,
+        options: &ExecOptions,
+    ) -> Result<ExecRestResponse, SnowflakeApiError> {
+        let base_rest_res = self.exec_arrow_raw(sql, Bindings::None, options).await?;
+        Ok(into_resp_type!(
+            &base_rest_res,
+            self.to_query_result(base_rest_res.data)?
+        ))
+    }
+
+    /// Executes a single query with bind parameters.
+    /// Returns raw bytes in the Arrow response
+    pub async fn exec_raw_with_params(
+        &self,
+        sql: &str,
+        params: impl Into<BindParams>,
+    ) -> Result<ProcessedRestResponse, SnowflakeApiError> {
         let put_re = Regex::new(r"(?i)^(?:/\*.*\*/\s*)*put\s+").unwrap();
+        let bindings = params
+            .into()
+            .to_bindings()
+            .map_or(Bindings::None, Bindings::Inline);
 
         // put commands go through a different flow and result is side-effect
         if put_re.is_match(sql) {
             log::info!("Detected PUT query");
+            if !matches!(bindings, Bindings::None) {
+                return Err(SnowflakeApiError::Unimplemented(
+                    "bind parameters in PUT statements".to_string(),
+                ));
+            }
             self.exec_put(sql).await
         } else {
-            self.exec_arrow_raw(sql).await
+            self.exec_arrow_raw(sql, bindings, &ExecOptions::default())
+                .await
+        }
+    }
+
+    /// Execute a single statement once for every row of the given columns, eg bulk `INSERT`.
+    /// Each inner `Vec` holds the values of one placeholder, all columns must be of the same length.
+    /// If there are more values than the stage binding threshold, they are uploaded
+    /// to the temporary stage with PUT and referenced from there.
+    pub async fn exec_array_bind(
+        &self,
+        sql: &str,
+        columns: Vec<Vec<BindValue>>,
+    ) -> Result<ExecRestResponse, SnowflakeApiError> {
+        let bindings = if bindings::array_bindings_count(&columns) > self.stage_binding_threshold {
+            let csv = bindings::to_array_bindings_csv(&columns)?;
+            Bindings::Stage(self.upload_bind_stage(csv).await?)
+        } else {
+            Bindings::Inline(bindings::to_array_bindings(&columns)?)
+        };
+
+        let base_rest_res = self
+            .exec_arrow_raw(sql, bindings, &ExecOptions::default())
+            .await?;
+        Ok(into_resp_type!(
+            &base_rest_res,
+            self.to_query_result(base_rest_res.data)?
+        ))
+    }
+
+    /// Same as [`SnowflakeApi::exec_array_bind`], with every batch column bound to a placeholder
+    pub async fn exec_record_batch_bind(
+        &self,
+        sql: &str,
+        batch: &RecordBatch,
+    ) -> Result<ExecRestResponse, SnowflakeApiError> {
+        let columns = bindings::columns_from_record_batch(batch)?;
+        self.exec_array_bind(sql, columns).await
+    }
+
+    /// Execute several `;`-separated statements in a single request.
+    /// `statement_count` must match the number of statements in `sql`, 0 allows any number of them.
+    /// Returns result of every statement, in the order they appear in `sql`.
+    pub async fn exec_multi(
+        &self,
+        sql: &str,
+        statement_count: usize,
+    ) -> Result<Vec<QueryResult>, SnowflakeApiError> {
+        let options = ExecOptions::default().with_parameter(MULTI_STATEMENT_COUNT, statement_count);
+        let request_id = Uuid::new_v4();
+        let resp = self
+            .abort_on_drop(request_id, async {
+                let resp = self
+                    .run_sql::<ExecResponse>(sql, Bindings::None, false, &options, request_id)
+                    .await?;
+                log::debug!("Got multi-statement response: {resp:?}");
+
+                self.wait_for_result(into_query_response(resp)?).await
+            })
+            .await?;
+        let sync_data = resp.data.as_sync()?;
+        let Some(result_ids) = sync_data.result_ids else {
+            return Err(SnowflakeApiError::UnexpectedResponse);
+        };
+
+        // statements have already finished, so each result is fetched in a single request
+        let mut results = vec![];
+        for query_id in result_ids.split(',').filter(|id| !id.is_empty()) {
+            results.push(self.fetch_result(query_id).await?);
         }
+
+        Ok(results)
+    }
+
+    /// Uploads CSV-serialized bindings to the temporary stage, returns stage location
+    async fn upload_bind_stage(&self, csv: String) -> Result<String, SnowflakeApiError> {
+        log::info!("Uploading array bindings to the stage");
+        let create_stage = format!(
+            "CREATE TEMPORARY STAGE IF NOT EXISTS {BIND_STAGE_NAME} \
+             file_format=(type=csv field_optionally_enclosed_by='\"')"
+        );
+        self.exec_arrow_raw(&create_stage, Bindings::None, &ExecOptions::default())
+            .await?;
+
+        let stage_location = format!("@{BIND_STAGE_NAME}/{}", Uuid::new_v4());
+        let local_path = std::env::temp_dir().join(format!("{}.csv", Uuid::new_v4()));
+        tokio::fs::write(&local_path, csv).await?;
+
+        let put = format!(
+            "PUT 'file://{}' '{stage_location}' auto_compress=false overwrite=true",
+            local_path.display()
+        );
+        let res = self.exec_put(&put).await;
+        if let Err(e) = tokio::fs::remove_file(&local_path).await {
+            log::warn!(
+                "Failed to remove bindings file {}: {e}",
+                local_path.display()
+            );
+        }
+        res?;
+
+        Ok(stage_location)
     }
 
     async fn exec_put(&self, sql: &str) -> Result<ProcessedRestResponse, SnowflakeApiError> {
+        let request_id = Uuid::new_v4();
         let resp = self
-            .run_sql::<ExecResponse>(sql, QueryType::JsonQuery)
+            .run_sql::<ExecResponse>(
+                sql,
+                Bindings::None,
+                false,
+                &ExecOptions::default().with_result_format(ResultFormat::Json),
+                request_id,
+            )
             .await?;
         log::debug!("Got PUT response: {:?}", resp);
 
         match resp {
             ExecResponse::Query(_) => Err(SnowflakeApiError::UnexpectedResponse),
             ExecResponse::PutGet(pg) => {
-                let res = into_resp_type!(
+                let mut res = into_resp_type!(
                     &pg,
                     RawQueryResult::Empty(EmptyJsonResult {
                         schema: None,
                         query_id: pg.data.query_id.clone(),
+                        statement_type_id: pg.data.statement_type_id,
                         send_result_time: pg.data.send_result_time,
                         query_context: pg.data.query_context.clone()
                     })
                 );
+                res.request_id = Some(request_id);
                 put::put(pg).await?;
                 Ok(res)
             }
@@ -480,103 +1000,209 @@ impl SnowflakeApi {
     /// Useful for debugging to get the straight query response
     #[cfg(debug_assertions)]
     pub async fn exec_response(&mut self, sql: &str) -> Result<ExecResponse, SnowflakeApiError> {
-        self.run_sql::<ExecResponse>(sql, QueryType::ArrowQuery)
-            .await
+        self.run_sql::<ExecResponse>(
+            sql,
+            Bindings::None,
+            false,
+            &ExecOptions::default(),
+            Uuid::new_v4(),
+        )
+        .await
     }
 
     /// Useful for debugging to get raw JSON response
     #[cfg(debug_assertions)]
     pub async fn exec_json(&mut self, sql: &str) -> Result<serde_json::Value, SnowflakeApiError> {
-        self.run_sql::<serde_json::Value>(sql, QueryType::JsonQuery)
-            .await
+        self.run_sql::<serde_json::Value>(
+            sql,
+            Bindings::None,
+            false,
+            &ExecOptions::default().with_result_format(ResultFormat::Json),
+            Uuid::new_v4(),
+        )
+        .await
+    }
+
+    async fn exec_arrow_raw(
+        &self,
+        sql: &str,
+        bindings: Bindings,
+        options: &ExecOptions,
+    ) -> Result<ProcessedRestResponse, SnowflakeApiError> {
+        let request_id = Uuid::new_v4();
+        let mut resp = self
+            .abort_on_drop(request_id, async {
+                let resp = self
+                    .run_sql::<ExecResponse>(sql, bindings, false, options, request_id)
+                    .await?;
+                log::debug!("Got query response: {resp:?}");
+
+                self.process_query_response(into_query_response(resp)?)
+                    .await
+            })
+            .await?;
+
+        resp.request_id = Some(request_id);
+        Ok(resp)
+    }
+
+    /// Abort the statement by the id of the request it was sent with,
+    /// see [`BaseRestResponse::request_id`] and [`QueryHandle::request_id`].
+    /// It doesn't have to be issued by this session.
+    pub async fn cancel(&self, request_id: Uuid) -> Result<(), SnowflakeApiError> {
+        cancel::abort_request(
+            &self.connection,
+            &self.session,
+            &self.account_identifier,
+            request_id,
+        )
+        .await
     }
 
-    async fn exec_arrow_raw(&self, sql: &str) -> Result<ProcessedRestResponse, SnowflakeApiError> {
+    /// Submit query for asynchronous execution and return right away, without waiting for the result.
+    /// Query keeps running on the server even if the handle is dropped,
+    /// the result could be collected later, even from another process, by the query id.
+    pub async fn submit(&self, sql: &str) -> Result<QueryHandle<'_>, SnowflakeApiError> {
+        let request_id = Uuid::new_v4();
         let resp = self
-            .run_sql::<ExecResponse>(sql, QueryType::ArrowQuery)
+            .run_sql::<ExecResponse>(
+                sql,
+                Bindings::None,
+                true,
+                &ExecOptions::default(),
+                request_id,
+            )
             .await?;
-        log::debug!("Got query response: {:?}", resp);
+        log::debug!("Got async query response: {resp:?}");
 
-        let orig_resp = match resp {
-            // processable response
-            ExecResponse::Query(qr) => Ok(qr),
-            ExecResponse::PutGet(_) => Err(SnowflakeApiError::UnexpectedResponse),
-            ExecResponse::Error(e) => Err(SnowflakeApiError::ApiError {
-                code: e.data.error_code,
-                message: e.message.unwrap_or_default(),
-                query_id: e.data.query_id,
+        let query_id = match into_query_response(resp)?.data {
+            QueryExecResponseData::Async(data) => data.query_id,
+            QueryExecResponseData::Sync(data) => data.query_id,
+        };
+
+        Ok(QueryHandle::new(self, query_id).with_request_id(request_id))
+    }
+
+    /// Handle to the query submitted earlier, possibly by another process or session
+    pub fn query_handle(&self, query_id: &str) -> QueryHandle<'_> {
+        QueryHandle::new(self, query_id.to_string())
+    }
+
+    /// Result of the query executed earlier, possibly by another process or session.
+    /// Query is not re-run, if it's still running this waits for it to finish.
+    pub async fn fetch_result(&self, query_id: &str) -> Result<QueryResult, SnowflakeApiError> {
+        self.to_query_result(self.fetch_result_raw(query_id).await?.data)
+    }
+
+    pub(crate) async fn fetch_result_raw(
+        &self,
+        query_id: &str,
+    ) -> Result<ProcessedRestResponse, SnowflakeApiError> {
+        let resp = self.query_result(query_id).await?;
+        self.process_query_response(resp).await
+    }
+
+    /// First response of the query result endpoint, might still be async if query is running
+    pub(crate) async fn query_result(
+        &self,
+        query_id: &str,
+    ) -> Result<QueryExecResponse, SnowflakeApiError> {
+        let resp = self
+            .poll::<ExecResponse>(
+                &format!("/queries/{query_id}/result"),
+                QueryType::ArrowQuery,
+            )
+            .await?;
+
+        into_query_response(resp)
+    }
+
+    /// Decodes Arrow chunks, converting JSON and normalizing Arrow if it was asked for
+    pub(crate) fn to_query_result(
+        &self,
+        raw: RawQueryResult,
+    ) -> Result<QueryResult, SnowflakeApiError> {
+        let result = match raw.deserialize_arrow()? {
+            QueryResult::Json(json) if self.json_as_arrow => QueryResult::Arrow(ArrowResult {
+                batches: vec![json.to_record_batch()?],
+                query_id: json.query_id,
+                statement_type_id: json.statement_type_id,
+                send_result_time: json.send_result_time,
+                query_context: json.query_context,
             }),
-        }?;
-        let mut resp = orig_resp.clone();
-        while resp.is_async() {
-            let async_data = resp.data.as_async()?;
-            resp = match self
-                .poll::<ExecResponse>(&async_data.get_result_url)
-                .await?
-            {
-                ExecResponse::Query(qr) => qr,
-                ExecResponse::PutGet(_) => return Err(SnowflakeApiError::UnexpectedResponse),
-                ExecResponse::Error(e) => {
-                    return Err(SnowflakeApiError::ApiError {
-                        code: e.data.error_code,
-                        message: e.message.unwrap_or_default(),
-                        query_id: e.data.query_id,
-                    })
-                }
-            };
-        }
+            result => result,
+        };
+
+        Ok(match result {
+            QueryResult::Arrow(mut arrow) if self.normalize_arrow => {
+                arrow.batches = arrow
+                    .batches
+                    .iter()
+                    .map(normalize_batch)
+                    .collect::<Result<_, _>>()?;
+                QueryResult::Arrow(arrow)
+            }
+            result => result,
+        })
+    }
+
+    /// Waits for the async query to finish and downloads the result
+    async fn process_query_response(
+        &self,
+        orig_resp: QueryExecResponse,
+    ) -> Result<ProcessedRestResponse, SnowflakeApiError> {
+        let resp = self.wait_for_result(orig_resp.clone()).await?;
 
         // if response was empty, base64 data is empty string
-        // todo: still return empty arrow batch with proper schema? (schema always included)
         // should be safe to ? here, as we've checked for async resp before
         let sync_data = resp.data.as_sync()?;
         let raw_query_res = if sync_data.returned == 0 {
             log::debug!("Got response with 0 rows");
-            let schema = if let Some(rowtype) = sync_data.rowtype {
-                Some(rowtype.into_iter().map(Into::into).collect())
-            } else {
-                None
-            };
-            RawQueryResult::Empty(EmptyJsonResult {
-                schema,
-                query_id: sync_data.query_id,
-                send_result_time: sync_data.send_result_time,
-                query_context: sync_data.query_context,
-            })
+            match sync_data.rowtype.filter(|rowtype| !rowtype.is_empty()) {
+                // result set without rows, keep its schema
+                Some(rowtype) => {
+                    let fields: Vec<FieldSchema> = rowtype.into_iter().map(Into::into).collect();
+                    RawQueryResult::Bytes(BytesResult {
+                        chunks: vec![],
+                        schema: Some(schema::raw_arrow_schema(&fields)),
+                        query_id: sync_data.query_id,
+                        statement_type_id: sync_data.statement_type_id,
+                        send_result_time: sync_data.send_result_time,
+                        query_context: sync_data.query_context,
+                    })
+                }
+                None => RawQueryResult::Empty(EmptyJsonResult {
+                    schema: None,
+                    query_id: sync_data.query_id,
+                    statement_type_id: sync_data.statement_type_id,
+                    send_result_time: sync_data.send_result_time,
+                    query_context: sync_data.query_context,
+                }),
+            }
         } else if let Some(value) = sync_data.rowset {
             log::debug!("Got JSON response");
-            let mut values: Vec<Value> = serde_json::from_value(value).unwrap();
+            let Value::Array(mut values) = value else {
+                return Err(SnowflakeApiError::BrokenResponse);
+            };
             for chunk in sync_data.chunks.iter() {
                 let bytes = self
                     .connection
                     .get_chunk(&chunk.url, &sync_data.chunk_headers)
                     .await?;
-                // Add a '[' at the beginning and ']' at the end of the byte stream
-                let mut bytes_with_brackets = Vec::new();
-                bytes_with_brackets.push(b'['); // Add opening bracket
-                bytes_with_brackets.extend_from_slice(&bytes); // Add the original bytes
-                bytes_with_brackets.push(b']'); // Add closing bracket
-
-                // Replace the invalid ',' between arrays with the valid array separator ", "
-                let json_str = String::from_utf8_lossy(&bytes_with_brackets);
-                let json_str = json_str.replace("], [", "],[");
-
-                // Now deserialize as a `Vec<Value>` since the entire data is now a valid JSON array
-                let chunk_values: Vec<Value> = serde_json::from_str(&json_str).unwrap();
-                values.extend(chunk_values);
+                values.extend(json::parse_json_chunk(&bytes)?);
             }
             // NOTE: json response could be chunked too. however, go clients should receive arrow by-default,
             // unless user sets session variable to return json. This case was added for debugging and status
             // information being passed through that fields.
-            let value = serde_json::to_value(values).unwrap();
             RawQueryResult::Json(JsonResult {
-                value,
+                value: Value::Array(values),
                 query_id: sync_data.query_id,
+                statement_type_id: sync_data.statement_type_id,
                 send_result_time: sync_data.send_result_time,
                 query_context: sync_data.query_context,
                 schema: sync_data
                     .rowtype
-                    .unwrap()
+                    .ok_or(SnowflakeApiError::BrokenResponse)?
                     .into_iter()
                     .map(Into::into)
                     .collect(),
@@ -589,17 +1215,19 @@ impl SnowflakeApi {
             }))
             .await?;
 
-            // fixme: should base64 chunk go first?
+            // inline rowset is the first part of the result, chunks follow it
             // fixme: if response is chunked is it both base64 + chunks or just chunks?
             if !base64.is_empty() {
                 log::debug!("Got base64 encoded response");
                 let bytes = Bytes::from(base64::engine::general_purpose::STANDARD.decode(base64)?);
-                chunks.push(bytes);
+                chunks.insert(0, bytes);
             }
 
             RawQueryResult::Bytes(BytesResult {
                 chunks,
+                schema: None,
                 query_id: sync_data.query_id,
+                statement_type_id: sync_data.statement_type_id,
                 send_result_time: sync_data.send_result_time,
                 query_context: sync_data.query_context,
             })
@@ -609,56 +1237,307 @@ impl SnowflakeApi {
         Ok(into_resp_type!(&orig_resp, raw_query_res))
     }
 
+    /// Polls result URL until the query is finished, backing off according to the poll policy
+    async fn wait_for_result(
+        &self,
+        mut resp: QueryExecResponse,
+    ) -> Result<QueryExecResponse, SnowflakeApiError> {
+        let started = Instant::now();
+        let mut delay = self.poll_policy.initial_delay;
+        while resp.is_async() {
+            let async_data = resp.data.as_async()?;
+            let deadline = self
+                .poll_policy
+                .deadline(async_data.query_aborts_after_secs);
+            if deadline.is_some_and(|deadline| started.elapsed() >= deadline) {
+                return Err(SnowflakeApiError::QueryTimeout {
+                    query_id: async_data.query_id,
+                });
+            }
+
+            // progress is best effort, monitoring failure doesn't fail the query
+            if let Some(callback) = &self.progress_callback {
+                match self.query_status(&async_data.query_id).await {
+                    Ok(status) => callback(&status),
+                    Err(e) => log::warn!(
+                        "Failed to get status of the query {}: {e}",
+                        async_data.query_id
+                    ),
+                }
+            }
+
+            let remaining =
+                deadline.map_or(delay, |deadline| deadline.saturating_sub(started.elapsed()));
+            tokio::time::sleep(delay.min(remaining)).await;
+            delay = self.poll_policy.next_delay(delay);
+
+            resp = into_query_response(
+                self.poll::<ExecResponse>(&async_data.get_result_url, QueryType::ArrowQuery)
+                    .await?,
+            )?;
+        }
+
+        Ok(resp)
+    }
+
     async fn run_sql<R: serde::de::DeserializeOwned>(
         &self,
         sql_text: &str,
-        query_type: QueryType,
+        bindings: Bindings,
+        async_exec: bool,
+        options: &ExecOptions,
+        request_id: Uuid,
     ) -> Result<R, SnowflakeApiError> {
         log::debug!("Executing: {}", sql_text);
 
-        let parts = self.session.get_token().await?;
-
+        let (bindings, bind_stage) = match bindings {
+            Bindings::None => (None, None),
+            Bindings::Inline(bindings) => (Some(bindings), None),
+            Bindings::Stage(location) => (None, Some(location)),
+        };
         let body = ExecRequest {
             sql_text: sql_text.to_string(),
-            async_exec: false,
-            sequence_id: parts.sequence_id,
+            async_exec,
+            sequence_id: 0,
             is_internal: false,
+            bindings,
+            bind_stage,
+            parameters: options.to_parameters(),
+            describe_only: options.describe_only,
         };
 
-        let resp = self
-            .connection
-            .request::<R>(
-                query_type,
-                &self.account_identifier,
-                &[],
-                Some(&parts.session_token_auth_header),
-                body,
-                None,
-            )
-            .await?;
-
-        Ok(resp)
+        self.request_with_session(
+            options.query_type(),
+            |parts| ExecRequest {
+                sequence_id: parts.sequence_id,
+                ..body.clone()
+            },
+            None,
+            request_id,
+        )
+        .await
     }
 
     async fn poll<R: serde::de::DeserializeOwned>(
         &self,
         get_result_url: &str,
+        query_type: QueryType,
     ) -> Result<R, SnowflakeApiError> {
         log::debug!("Polling: {}", get_result_url);
 
-        let parts = self.session.get_token().await?;
-        let resp = self
-            .connection
-            .request::<R>(
-                QueryType::ArrowQuery,
-                &self.account_identifier,
+        self.request_with_session(
+            query_type,
+            |_| EmptyRequest,
+            Some(get_result_url),
+            Uuid::new_v4(),
+        )
+        .await
+    }
+
+    async fn request_with_session<R: serde::de::DeserializeOwned, B: serde::Serialize>(
+        &self,
+        query_type: QueryType,
+        body: impl Fn(&AuthParts) -> B,
+        url_override: Option<&str>,
+        request_id: Uuid,
+    ) -> Result<R, SnowflakeApiError> {
+        request_with_session(
+            &self.connection,
+            &self.session,
+            &self.account_identifier,
+            query_type,
+            body,
+            url_override,
+            request_id,
+        )
+        .await
+    }
+}
+
+/// Sends the request with the session token. If server rejects the token as expired or invalid,
+/// session is renewed or started anew and the request is replayed once, with the same request id.
+async fn request_with_session<R: serde::de::DeserializeOwned, B: serde::Serialize>(
+    connection: &Connection,
+    session: &Session,
+    account_identifier: &str,
+    query_type: QueryType,
+    body: impl Fn(&AuthParts) -> B,
+    url_override: Option<&str>,
+    request_id: Uuid,
+) -> Result<R, SnowflakeApiError> {
+    let mut replayed = false;
+    loop {
+        let parts = session.get_token().await?;
+        let resp = connection
+            .request_with_id::<Value>(
+                query_type,
+                account_identifier,
                 &[],
                 Some(&parts.session_token_auth_header),
-                EmptyRequest,
-                Some(get_result_url),
+                body(&parts),
+                url_override,
+                request_id,
             )
             .await?;
 
-        Ok(resp)
+        match session_error_code(&resp) {
+            Some(code) if !replayed => {
+                log::info!("Session token was rejected with code {code}, refreshing the session");
+                session.refresh(&parts, code).await?;
+                replayed = true;
+            }
+            Some(code) => {
+                return Err(SnowflakeApiError::ApiError {
+                    code: code.to_string(),
+                    message: resp["message"].as_str().unwrap_or_default().to_string(),
+                    query_id: String::new(),
+                })
+            }
+            None => {
+                return R::deserialize(&resp)
+                    .map_err(|_| ConnectionError::UnexpectedResponse(resp.to_string()).into())
+            }
+        }
+    }
+}
+
+/// Error code of the response rejected because of the session token
+fn session_error_code(resp: &Value) -> Option<&str> {
+    if resp["success"].as_bool().unwrap_or(true) {
+        return None;
+    }
+    resp["code"]
+        .as_str()
+        .filter(|code| session::is_session_error(code))
+}
+
+/// Unwraps query response, turning server-side errors into [`SnowflakeApiError::ApiError`]
+fn into_query_response(resp: ExecResponse) -> Result<QueryExecResponse, SnowflakeApiError> {
+    match resp {
+        // processable response
+        ExecResponse::Query(qr) => Ok(qr),
+        ExecResponse::PutGet(_) => Err(SnowflakeApiError::UnexpectedResponse),
+        ExecResponse::Error(e) => Err(SnowflakeApiError::ApiError {
+            code: e.data.error_code,
+            message: e.message.unwrap_or_default(),
+            query_id: e.data.query_id,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer};
+
+    use super::*;
+    use crate::test_utils::{
+        api, json_result_data, mount_login, running_response, success_response,
+    };
+
+    fn number_result(query_id: &str, value: i64) -> Value {
+        json_result_data(
+            query_id,
+            &json!([{"name": "N", "type": "fixed", "precision": 18, "scale": 0, "nullable": false}]),
+            &json!([[value.to_string()]]),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_exec_multi_fetches_each_result_in_order() {
+        let result_ids = [
+            "01b2c3d4-0000-0001-0000-000000000002",
+            "01b2c3d4-0000-0001-0000-000000000003",
+        ];
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        let mut multi = json_result_data(
+            "01b2c3d4-0000-0001-0000-000000000001",
+            &json!([{"name": "multiple statement execution", "type": "text", "nullable": false}]),
+            &json!([["Multiple statements executed successfully."]]),
+        );
+        multi["resultIds"] = Value::from(result_ids.join(","));
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .and(body_partial_json(json!({
+                "sqlText": "SELECT 1; SELECT 2",
+                "parameters": {"MULTI_STATEMENT_COUNT": 2}
+            })))
+            .respond_with(success_response(&multi))
+            .expect(1)
+            .mount(&server)
+            .await;
+        for (value, query_id) in (1..).zip(result_ids) {
+            Mock::given(path(format!("/queries/{query_id}/result")))
+                .respond_with(success_response(&number_result(query_id, value)))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let results = api(&server)
+            .exec_multi("SELECT 1; SELECT 2", 2)
+            .await
+            .unwrap();
+        let values: Vec<i64> = results
+            .iter()
+            .map(|result| result.rows().unwrap()[0].get("N").unwrap())
+            .collect();
+        assert_eq!(values, vec![1, 2]);
+
+        let fetched: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.url.path().to_string())
+            .filter(|p| p.ends_with("/result"))
+            .collect();
+        assert_eq!(
+            fetched,
+            result_ids.map(|id| format!("/queries/{id}/result"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_result_of_finished_query() {
+        let query_id = "01b2c3d4-0000-0001-0000-000000000001";
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(method("GET"))
+            .and(path(format!("/queries/{query_id}/result")))
+            .respond_with(success_response(&number_result(query_id, 42)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = api(&server).fetch_result(query_id).await.unwrap();
+        assert_eq!(result.rows().unwrap()[0].get::<i64>("N").unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_result_waits_for_running_query() {
+        let query_id = "01b2c3d4-0000-0001-0000-000000000001";
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(method("GET"))
+            .and(path(format!("/queries/{query_id}/result")))
+            .respond_with(running_response(query_id))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/queries/{query_id}/result")))
+            .respond_with(success_response(&number_result(query_id, 42)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut api = api(&server);
+        api.poll_policy.initial_delay = Duration::from_millis(1);
+        let result = api.fetch_result(query_id).await.unwrap();
+        assert_eq!(result.rows().unwrap()[0].get::<i64>("N").unwrap(), 42);
     }
 }
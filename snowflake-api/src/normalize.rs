@@ -0,0 +1,394 @@
+//! Snowflake encodes some types in the Arrow result in its own way, using the field metadata
+//! to describe them: numbers are scaled integers, time is scaled integer and timestamps are
+//! either scaled integers or structs of epoch, fraction and timezone.
+//! This turns them into the standard Arrow types.
+
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, Decimal128Array, Int32Array, Int64Array, StringArray, StructArray,
+    Time64NanosecondArray, TimestampNanosecondArray,
+};
+use arrow::buffer::NullBuffer;
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Int32Type, Int64Type, Schema};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+
+use crate::cell::format_decimal;
+use crate::variant;
+
+const NANOS_SCALE: i64 = 9;
+/// Max precision of the integer which fits into `Int64`
+pub(crate) const INT64_MAX_PRECISION: u8 = 18;
+/// Timezone offset is stored in minutes, shifted by this to be positive
+const TIMEZONE_OFFSET_BIAS: i32 = 1440;
+
+/// Rewrite columns of the batch decoded from the Snowflake Arrow result into standard Arrow types,
+/// based on the `logicalType`, `scale` and `precision` field metadata:
+/// - `FIXED` with non-zero scale becomes `Decimal128(precision, scale)`, otherwise `Int64`
+/// - `DATE` becomes `Date32`
+/// - `TIME` becomes `Time64(Nanosecond)`
+/// - `TIMESTAMP_NTZ` becomes `Timestamp(Nanosecond, None)`
+/// - `TIMESTAMP_LTZ` and `TIMESTAMP_TZ` become `Timestamp(Nanosecond, "UTC")`, original timezone
+///   offset of `TIMESTAMP_TZ` is not kept
+/// - `DECFLOAT` becomes exact decimal text, eg `1.25` or `12E+100`, as it doesn't fit into `Decimal128`
+/// - `VARIANT`, `OBJECT` and `ARRAY` stay JSON text, tagged with the `arrow.json` extension type
+///
+/// Columns of other types or without metadata are left as is, so it's safe to call it more than once.
+pub fn normalize_batch(batch: &RecordBatch) -> Result<RecordBatch, ArrowError> {
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(batch.num_columns());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        let column = normalize_column(field, column)?;
+        let mut field = field
+            .as_ref()
+            .clone()
+            .with_data_type(column.data_type().clone());
+        if variant::is_json_field(&field) {
+            let mut metadata = field.metadata().clone();
+            variant::add_json_extension(&mut metadata);
+            field = field.with_metadata(metadata);
+        }
+        fields.push(field);
+        columns.push(column);
+    }
+
+    RecordBatch::try_new(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )
+}
+
+fn normalize_column(field: &Field, column: &ArrayRef) -> Result<ArrayRef, ArrowError> {
+    let Some(logical_type) = field.metadata().get("logicalType") else {
+        return Ok(Arc::clone(column));
+    };
+    let scale = metadata_value(field, "scale")?.unwrap_or(0);
+
+    match logical_type.as_str() {
+        "FIXED" => {
+            let precision = metadata_value(field, "precision")?.unwrap_or(38);
+            fixed(column, precision, scale)
+        }
+        "DATE" => cast(column, &DataType::Date32),
+        "TIME" => time(column, scale),
+        "TIMESTAMP_NTZ" => timestamp(column, scale, None),
+        "TIMESTAMP_LTZ" => timestamp(column, scale, Some("UTC")),
+        "TIMESTAMP_TZ" => timestamp_tz(column, scale),
+        "DECFLOAT" => decfloat(column),
+        _ => Ok(Arc::clone(column)),
+    }
+}
+
+fn metadata_value(field: &Field, key: &str) -> Result<Option<i64>, ArrowError> {
+    field
+        .metadata()
+        .get(key)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                ArrowError::ParseError(format!(
+                    "Invalid `{key}` metadata of the field `{}`: {value}",
+                    field.name()
+                ))
+            })
+        })
+        .transpose()
+}
+
+fn fixed(column: &ArrayRef, precision: i64, scale: i64) -> Result<ArrayRef, ArrowError> {
+    if !column.data_type().is_integer() {
+        // already decimal or float
+        return Ok(Arc::clone(column));
+    }
+    let precision = u8::try_from(precision)
+        .map_err(|_| ArrowError::InvalidArgumentError(format!("Invalid precision: {precision}")))?;
+    let scale = i8::try_from(scale)
+        .map_err(|_| ArrowError::InvalidArgumentError(format!("Invalid scale: {scale}")))?;
+
+    let values = cast(column, &DataType::Int64)?;
+    if scale == 0 && precision <= INT64_MAX_PRECISION {
+        return Ok(values);
+    }
+
+    // integer is the unscaled decimal value, so it's reinterpreted rather than cast
+    let decimals: Decimal128Array = values.as_primitive::<Int64Type>().unary(i128::from);
+    Ok(Arc::new(
+        decimals.with_precision_and_scale(precision, scale)?,
+    ))
+}
+
+fn time(column: &ArrayRef, scale: i64) -> Result<ArrayRef, ArrowError> {
+    if !column.data_type().is_integer() {
+        return Ok(Arc::clone(column));
+    }
+    let values = cast(column, &DataType::Int64)?;
+    let nanos: Time64NanosecondArray = values
+        .as_primitive::<Int64Type>()
+        .try_unary(|v| scaled_to_nanos(v, scale))?;
+    Ok(Arc::new(nanos))
+}
+
+fn timestamp(column: &ArrayRef, scale: i64, tz: Option<&str>) -> Result<ArrayRef, ArrowError> {
+    let nanos = match column.data_type() {
+        DataType::Int64 => column
+            .as_primitive::<Int64Type>()
+            .try_unary(|v| scaled_to_nanos(v, scale))?,
+        DataType::Struct(_) => {
+            let column = column.as_struct();
+            let epoch = struct_field::<Int64Type>(column, "epoch")?;
+            let fraction = struct_field::<Int32Type>(column, "fraction")?;
+            epoch_fraction_to_nanos(column, epoch, fraction)?
+        }
+        _ => return Ok(Arc::clone(column)),
+    };
+
+    Ok(Arc::new(nanos.with_timezone_opt(tz)))
+}
+
+/// Timezone offset isn't kept, as there is no Arrow type for per-value timezones
+fn timestamp_tz(column: &ArrayRef, scale: i64) -> Result<ArrayRef, ArrowError> {
+    let DataType::Struct(fields) = column.data_type() else {
+        return Ok(Arc::clone(column));
+    };
+    let column = column.as_struct();
+    let epoch = struct_field::<Int64Type>(column, "epoch")?;
+    // validate the layout, even though the offset is not needed for UTC instant
+    let timezone = struct_field::<Int32Type>(column, "timezone")?;
+    if let Some(offset) = timezone
+        .iter()
+        .flatten()
+        .find(|offset| !(0..=2 * TIMEZONE_OFFSET_BIAS).contains(offset))
+    {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Invalid timezone offset: {offset}"
+        )));
+    }
+
+    let nanos = if fields.len() == 3 {
+        let fraction = struct_field::<Int32Type>(column, "fraction")?;
+        epoch_fraction_to_nanos(column, epoch, fraction)?
+    } else {
+        // with low scale epoch is scaled and there is no fraction
+        let nanos: TimestampNanosecondArray = epoch.try_unary(|v| scaled_to_nanos(v, scale))?;
+        let nulls = NullBuffer::union(nanos.nulls(), column.nulls());
+        TimestampNanosecondArray::new(nanos.values().clone(), nulls)
+    };
+
+    Ok(Arc::new(nanos.with_timezone("UTC")))
+}
+
+fn struct_field<'a, T: arrow::datatypes::ArrowPrimitiveType>(
+    column: &'a StructArray,
+    name: &str,
+) -> Result<&'a arrow::array::PrimitiveArray<T>, ArrowError> {
+    column
+        .column_by_name(name)
+        .and_then(|c| c.as_primitive_opt::<T>())
+        .ok_or_else(|| {
+            ArrowError::SchemaError(format!(
+                "Missing or invalid `{name}` field of the timestamp"
+            ))
+        })
+}
+
+fn epoch_fraction_to_nanos(
+    column: &StructArray,
+    epoch: &Int64Array,
+    fraction: &Int32Array,
+) -> Result<TimestampNanosecondArray, ArrowError> {
+    (0..column.len())
+        .map(|i| {
+            if column.is_null(i) {
+                return Ok(None);
+            }
+            epoch
+                .value(i)
+                .checked_mul(1_000_000_000)
+                .and_then(|v| v.checked_add(i64::from(fraction.value(i))))
+                .map(Some)
+                .ok_or_else(overflow)
+        })
+        .collect()
+}
+
+fn scaled_to_nanos(value: i64, scale: i64) -> Result<i64, ArrowError> {
+    let exp = u32::try_from(NANOS_SCALE - scale)
+        .map_err(|_| ArrowError::InvalidArgumentError(format!("Invalid scale: {scale}")))?;
+    value.checked_mul(10_i64.pow(exp)).ok_or_else(overflow)
+}
+
+/// `DECFLOAT` is a struct of `exponent` and big-endian two's complement `significand`
+fn decfloat(column: &ArrayRef) -> Result<ArrayRef, ArrowError> {
+    let Some(column) = column.as_struct_opt() else {
+        return Ok(Arc::clone(column));
+    };
+    let exponent = column
+        .column_by_name("exponent")
+        .map(|c| cast(c, &DataType::Int32))
+        .transpose()?;
+    let significand = column
+        .column_by_name("significand")
+        .and_then(|c| c.as_binary_opt::<i32>());
+    let (Some(exponent), Some(significand)) = (exponent, significand) else {
+        return Err(ArrowError::SchemaError(
+            "Missing or invalid fields of the decfloat".to_string(),
+        ));
+    };
+    let exponent = exponent.as_primitive::<Int32Type>();
+
+    let text: StringArray = (0..column.len())
+        .map(|i| {
+            if column.is_null(i) {
+                return Ok(None);
+            }
+            let value = significand_value(significand.value(i))?;
+            Ok(Some(match i8::try_from(-exponent.value(i)) {
+                Ok(scale) => format_decimal(value, scale),
+                Err(_) => format!("{value}E{}", exponent.value(i)),
+            }))
+        })
+        .collect::<Result<_, ArrowError>>()?;
+    Ok(Arc::new(text))
+}
+
+fn significand_value(bytes: &[u8]) -> Result<i128, ArrowError> {
+    if bytes.len() > 16 {
+        return Err(ArrowError::ComputeError(
+            "Decfloat significand doesn't fit into 128 bits".to_string(),
+        ));
+    }
+    let sign = if bytes.first().is_some_and(|b| b & 0x80 != 0) {
+        0xFF
+    } else {
+        0
+    };
+    let mut buf = [sign; 16];
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    Ok(i128::from_be_bytes(buf))
+}
+
+fn overflow() -> ArrowError {
+    ArrowError::ComputeError("Value doesn't fit into nanosecond timestamp".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Date32Array;
+    use arrow::datatypes::{Decimal128Type, TimeUnit};
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::RawQueryResult;
+
+    /// Decode and normalize the Arrow IPC payload in the Snowflake layout, see `tests/fixtures`
+    fn normalize_payload(payload: &'static [u8]) -> RecordBatch {
+        let batches = RawQueryResult::bytes_to_batches(Bytes::from_static(payload)).unwrap();
+        let batch = normalize_batch(&batches[0]).unwrap();
+        // already normalized batch is left as is
+        assert_eq!(normalize_batch(&batch).unwrap(), batch);
+        batch
+    }
+
+    fn timestamps(tz: Option<&str>, values: Vec<Option<i64>>) -> TimestampNanosecondArray {
+        TimestampNanosecondArray::from(values).with_timezone_opt(tz)
+    }
+
+    fn assert_timestamps(batch: &RecordBatch, tz: Option<&str>) {
+        let millis = timestamps(
+            tz,
+            vec![Some(1_700_000_000_123_000_000), Some(-1_500_000_000), None],
+        );
+        let nanos = timestamps(
+            tz,
+            vec![Some(1_700_000_000_123_456_789), Some(-500_000_000), None],
+        );
+        assert_eq!(batch.column(0).as_ref(), &millis as &dyn Array);
+        assert_eq!(batch.column(1).as_ref(), &nanos as &dyn Array);
+        assert_eq!(
+            batch.schema().field(1).data_type(),
+            &DataType::Timestamp(TimeUnit::Nanosecond, tz.map(Into::into))
+        );
+    }
+
+    #[test]
+    fn test_normalize_fixed() {
+        let batch = normalize_payload(include_bytes!("../tests/fixtures/fixed.arrow"));
+
+        let price = batch.column(0).as_primitive::<Decimal128Type>();
+        assert_eq!(price.data_type(), &DataType::Decimal128(10, 2));
+        assert_eq!(price.value_as_string(0), "123.45");
+        assert_eq!(price.value_as_string(1), "-0.05");
+        assert!(price.is_null(2));
+
+        assert_eq!(
+            batch.column(1).as_ref(),
+            &Int64Array::from(vec![Some(7), Some(-1), None]) as &dyn Array
+        );
+        assert_eq!(
+            batch.column(2).as_ref(),
+            &Int64Array::from(vec![Some(9_007_199_254_740_993), Some(0), None]) as &dyn Array
+        );
+
+        let total = batch.column(3).as_primitive::<Decimal128Type>();
+        assert_eq!(total.data_type(), &DataType::Decimal128(38, 0));
+        assert_eq!(total.value_as_string(1), "-4000000000");
+
+        // metadata is kept
+        assert_eq!(batch.schema().field(0).metadata()["logicalType"], "FIXED");
+    }
+
+    #[test]
+    fn test_normalize_time() {
+        let batch = normalize_payload(include_bytes!("../tests/fixtures/time.arrow"));
+
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &Time64NanosecondArray::from(vec![Some(45_296_789_000_000), Some(0), None])
+                as &dyn Array
+        );
+        assert_eq!(
+            batch.column(1).as_ref(),
+            &Time64NanosecondArray::from(vec![
+                Some(45_296_123_456_789),
+                Some(86_399_999_999_999),
+                None
+            ]) as &dyn Array
+        );
+    }
+
+    #[test]
+    fn test_normalize_timestamp_ntz() {
+        let batch = normalize_payload(include_bytes!("../tests/fixtures/timestamp_ntz.arrow"));
+        assert_timestamps(&batch, None);
+    }
+
+    #[test]
+    fn test_normalize_timestamp_ltz() {
+        let batch = normalize_payload(include_bytes!("../tests/fixtures/timestamp_ltz.arrow"));
+        assert_timestamps(&batch, Some("UTC"));
+    }
+
+    #[test]
+    fn test_normalize_timestamp_tz() {
+        let batch = normalize_payload(include_bytes!("../tests/fixtures/timestamp_tz.arrow"));
+        assert_timestamps(&batch, Some("UTC"));
+    }
+
+    #[test]
+    fn test_normalize_date() {
+        let day = Field::new("DAY", DataType::Int32, true)
+            .with_metadata([("logicalType".to_string(), "DATE".to_string())].into());
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![day])),
+            vec![Arc::new(Int32Array::from(vec![Some(19_675), None]))],
+        )
+        .unwrap();
+
+        assert_eq!(
+            normalize_batch(&batch).unwrap().column(0).as_ref(),
+            &Date32Array::from(vec![Some(19_675), None]) as &dyn Array
+        );
+    }
+}
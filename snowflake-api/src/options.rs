@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::connection::QueryType;
+
+/// Format the server is asked to return the result in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResultFormat {
+    #[default]
+    Arrow,
+    Json,
+}
+
+/// Settings applied to a single statement only, session state stays untouched.
+/// See [`SnowflakeApi::exec_with`](crate::SnowflakeApi::exec_with).
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct ExecOptions {
+    /// `QUERY_TAG`, visible in the query history
+    pub query_tag: Option<String>,
+    /// `STATEMENT_TIMEOUT_IN_SECONDS`, statement is aborted by the server after it.
+    /// Rounded up to whole seconds, so sub-second timeout becomes 1s rather than 0, which means no timeout.
+    pub statement_timeout: Option<Duration>,
+    pub result_format: ResultFormat,
+    /// Only compile the statement and return result schema, without executing it
+    pub describe_only: bool,
+    /// Any other statement-level parameters, eg `TIMEZONE` or `BINARY_OUTPUT_FORMAT`
+    pub parameters: HashMap<String, Value>,
+}
+
+impl ExecOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_query_tag(mut self, tag: impl Into<String>) -> Self {
+        self.query_tag = Some(tag.into());
+        self
+    }
+
+    pub fn with_statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_result_format(mut self, format: ResultFormat) -> Self {
+        self.result_format = format;
+        self
+    }
+
+    pub fn with_describe_only(mut self, describe_only: bool) -> Self {
+        self.describe_only = describe_only;
+        self
+    }
+
+    pub fn with_parameter(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.insert(name.into(), value.into());
+        self
+    }
+
+    pub(crate) fn query_type(&self) -> QueryType {
+        match self.result_format {
+            ResultFormat::Arrow => QueryType::ArrowQuery,
+            ResultFormat::Json => QueryType::JsonQuery,
+        }
+    }
+
+    /// Content of the request `parameters` map, dedicated options take precedence
+    pub(crate) fn to_parameters(&self) -> HashMap<String, Value> {
+        let mut parameters = self.parameters.clone();
+        if let Some(tag) = &self.query_tag {
+            parameters.insert("QUERY_TAG".to_string(), Value::from(tag.as_str()));
+        }
+        if let Some(timeout) = self.statement_timeout {
+            parameters.insert(
+                "STATEMENT_TIMEOUT_IN_SECONDS".to_string(),
+                Value::from(timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0)),
+            );
+        }
+        parameters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_to_parameters() {
+        let options = ExecOptions::new()
+            .with_parameter("TIMEZONE", "UTC")
+            .with_parameter("QUERY_TAG", "overridden")
+            .with_query_tag("nightly-etl")
+            .with_statement_timeout(Duration::from_secs(30))
+            .with_result_format(ResultFormat::Json);
+
+        let parameters = options.to_parameters();
+        assert_eq!(parameters["TIMEZONE"], "UTC");
+        assert_eq!(parameters["QUERY_TAG"], "nightly-etl");
+        assert_eq!(parameters["STATEMENT_TIMEOUT_IN_SECONDS"], 30);
+        assert!(matches!(options.query_type(), QueryType::JsonQuery));
+    }
+
+    #[test]
+    fn test_statement_timeout_rounded_up() {
+        let timeout = |timeout| {
+            ExecOptions::new()
+                .with_statement_timeout(timeout)
+                .to_parameters()["STATEMENT_TIMEOUT_IN_SECONDS"]
+                .clone()
+        };
+        assert_eq!(timeout(Duration::from_millis(500)), 1);
+        assert_eq!(timeout(Duration::from_millis(1500)), 2);
+        assert_eq!(timeout(Duration::from_secs(2)), 2);
+    }
+}
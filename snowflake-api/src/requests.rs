@@ -1,15 +1,53 @@
+use std::collections::HashMap;
+
 use serde::Serialize;
 
 #[derive(Serialize, Debug)]
 pub struct EmptyRequest;
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ExecRequest {
     pub sql_text: String,
     pub async_exec: bool,
     pub sequence_id: u64,
     pub is_internal: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bindings: Option<HashMap<String, BindParameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind_stage: Option<String>,
+    /// Statement-level parameters, eg `MULTI_STATEMENT_COUNT`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub parameters: HashMap<String, serde_json::Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub describe_only: bool,
+}
+
+/// Bind values sent along with the statement
+#[derive(Debug, Default)]
+pub enum Bindings {
+    #[default]
+    None,
+    /// Values are inlined into the `bindings` map
+    Inline(HashMap<String, BindParameter>),
+    /// Values were uploaded to the stage beforehand and are referenced by `bindStage`
+    Stage(String),
+}
+
+/// Single entry of the `bindings` map, eg `"1": {"type": "FIXED", "value": "42"}`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BindParameter {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub value: BindParameterValue,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum BindParameterValue {
+    Single(Option<String>),
+    /// Array binding, one value per row
+    Array(Vec<Option<String>>),
 }
 
 #[derive(Serialize, Debug)]
@@ -20,6 +58,9 @@ pub struct LoginRequest<T> {
 pub type PasswordLoginRequest = LoginRequest<PasswordRequestData>;
 #[cfg(feature = "cert-auth")]
 pub type CertLoginRequest = LoginRequest<CertRequestData>;
+pub type OAuthLoginRequest = LoginRequest<OAuthRequestData>;
+pub type AuthenticatorRequest = LoginRequest<AuthenticatorRequestData>;
+pub type ExternalBrowserLoginRequest = LoginRequest<ExternalBrowserRequestData>;
 
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -37,6 +78,8 @@ pub struct LoginRequestCommon {
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct SessionParameters {
     pub client_validate_default_parameters: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub client_session_keep_alive: bool,
 }
 
 #[derive(Serialize, Debug)]
@@ -65,9 +108,44 @@ pub struct CertRequestData {
     pub token: String,
 }
 
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct OAuthRequestData {
+    #[serde(flatten)]
+    pub login_request_common: LoginRequestCommon,
+    pub authenticator: String,
+    pub token: String,
+}
+
+/// Asks for the SSO URL, identity provider redirects the browser to the given localhost port
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct AuthenticatorRequestData {
+    #[serde(flatten)]
+    pub login_request_common: LoginRequestCommon,
+    pub authenticator: String,
+    pub browser_mode_redirect_port: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ExternalBrowserRequestData {
+    #[serde(flatten)]
+    pub login_request_common: LoginRequestCommon,
+    pub authenticator: String,
+    pub token: String,
+    pub proof_key: String,
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RenewSessionRequest {
     pub old_session_token: String,
     pub request_type: String,
 }
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AbortRequest {
+    pub request_id: String,
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use serde::Deserialize;
+use uuid::Uuid;
 
 use crate::{QueryResult, RawQueryResult, SnowflakeApiError};
 
@@ -42,6 +43,10 @@ pub struct BaseRestResponse<D> {
     pub message: Option<String>,
     pub success: bool,
     pub data: D,
+    /// Id of the request the statement was sent with, to cancel it by, see [`crate::SnowflakeApi::cancel`].
+    /// Only set on the statement results.
+    #[serde(skip)]
+    pub request_id: Option<Uuid>,
 }
 
 impl<D> BaseRestResponse<D> {
@@ -59,6 +64,9 @@ pub type LoginResponse = BaseRestResponse<LoginResponseData>;
 pub type RenewSessionResponse = BaseRestResponse<RenewSessionResponseData>;
 // Data should be always `null` on successful close session response
 pub type CloseSessionResponse = BaseRestResponse<Option<()>>;
+// Data is `null` on success
+pub type AbortRequestResponse = BaseRestResponse<serde_json::Value>;
+pub type MonitoringResponse = BaseRestResponse<MonitoringResponseData>;
 pub type ProcessedRestResponse = BaseRestResponse<RawQueryResult>;
 pub type ExecRestResponse = BaseRestResponse<QueryResult>;
 
@@ -69,6 +77,7 @@ macro_rules! into_resp_type {
             code: $base_res.code.clone(),
             message: $base_res.message.clone(),
             success: $base_res.success,
+            request_id: $base_res.request_id,
             data: $data,
         }
     };
@@ -129,7 +138,8 @@ pub struct SessionInfo {
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthenticatorResponseData {
-    pub token_url: String,
+    // only set for native Okta SSO
+    pub token_url: Option<String>,
     pub sso_url: String,
     pub proof_key: String,
 }
@@ -177,6 +187,76 @@ pub struct AsyncQueryExecResponseData {
     pub query_aborts_after_secs: i64,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct MonitoringResponseData {
+    // empty if query is not yet registered
+    #[serde(default)]
+    pub queries: Vec<QueryMonitoringData>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryMonitoringData {
+    pub id: String,
+    pub status: QueryState,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    // epoch millis
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    // millis
+    pub total_duration: Option<i64>,
+}
+
+/// Query execution state as reported by the monitoring endpoint
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum QueryState {
+    Running,
+    Aborting,
+    Success,
+    FailedWithError,
+    Aborted,
+    Queued,
+    FailedWithIncident,
+    Disconnected,
+    ResumingWarehouse,
+    // sic, server spells it this way
+    #[serde(rename = "QUEUED_REPARING_WAREHOUSE")]
+    QueuedRepairingWarehouse,
+    Restarted,
+    Blocked,
+    NoData,
+    #[serde(other)]
+    Unknown,
+}
+
+impl QueryState {
+    /// Query is executing or waits to be executed
+    pub fn is_running(self) -> bool {
+        matches!(
+            self,
+            Self::Running
+                | Self::Queued
+                | Self::ResumingWarehouse
+                | Self::QueuedRepairingWarehouse
+                | Self::NoData
+        )
+    }
+
+    pub fn is_error(self) -> bool {
+        matches!(
+            self,
+            Self::Aborting
+                | Self::FailedWithError
+                | Self::Aborted
+                | Self::FailedWithIncident
+                | Self::Disconnected
+                | Self::Blocked
+        )
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryContextEntry {
@@ -214,7 +294,7 @@ pub struct SyncQueryExecResponseData {
     pub final_role_name: Option<String>,      // unused in .NET
     // only present on SELECT queries
     pub number_of_binds: Option<i32>, // unused in .NET
-    // todo: deserialize into enum
+    /// See [`StatementType`](crate::StatementType)
     pub statement_type_id: Option<i64>,
     pub version: Option<i64>,
     // if response is chunked
@@ -244,11 +324,28 @@ pub struct ExecResponseRowType {
     pub scale: Option<i64>,
     pub precision: Option<i64>,
     pub nullable: bool,
+    #[serde(rename = "vectorDimension")]
+    pub vector_dimension: Option<i64>,
+    /// Element type of `VECTOR`, fields of structured types
+    #[serde(default)]
+    pub fields: Vec<ExecResponseFieldType>,
 }
 
-// fixme: is it good idea to keep this as an enum if more types could be added in future?
 #[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "snake_case")]
+pub struct ExecResponseFieldType {
+    #[serde(rename = "fieldName")]
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: SnowflakeType,
+    pub scale: Option<i64>,
+    pub precision: Option<i64>,
+    #[serde(default)]
+    pub nullable: bool,
+}
+
+/// Types newer than the client are kept as `Unknown`, so they don't break the response parsing
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String")]
 pub enum SnowflakeType {
     Fixed,
     Real,
@@ -263,6 +360,66 @@ pub enum SnowflakeType {
     Time,
     Boolean,
     Array,
+    Geography,
+    Geometry,
+    Vector,
+    Decfloat,
+    Map,
+    /// Uppercase type name
+    Unknown(String),
+}
+
+impl From<String> for SnowflakeType {
+    fn from(value: String) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "fixed" => Self::Fixed,
+            "real" => Self::Real,
+            "text" => Self::Text,
+            "date" => Self::Date,
+            "variant" => Self::Variant,
+            "timestamp_ltz" => Self::TimestampLtz,
+            "timestamp_ntz" => Self::TimestampNtz,
+            "timestamp_tz" => Self::TimestampTz,
+            "object" => Self::Object,
+            "binary" => Self::Binary,
+            "time" => Self::Time,
+            "boolean" => Self::Boolean,
+            "array" => Self::Array,
+            "geography" => Self::Geography,
+            "geometry" => Self::Geometry,
+            "vector" => Self::Vector,
+            "decfloat" => Self::Decfloat,
+            "map" => Self::Map,
+            _ => Self::Unknown(value.to_ascii_uppercase()),
+        }
+    }
+}
+
+impl SnowflakeType {
+    /// Type name as it's spelled by Snowflake, eg `TIMESTAMP_NTZ`
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Fixed => "FIXED",
+            Self::Real => "REAL",
+            Self::Text => "TEXT",
+            Self::Date => "DATE",
+            Self::Variant => "VARIANT",
+            Self::TimestampLtz => "TIMESTAMP_LTZ",
+            Self::TimestampNtz => "TIMESTAMP_NTZ",
+            Self::TimestampTz => "TIMESTAMP_TZ",
+            Self::Object => "OBJECT",
+            Self::Binary => "BINARY",
+            Self::Time => "TIME",
+            Self::Boolean => "BOOLEAN",
+            Self::Array => "ARRAY",
+            Self::Geography => "GEOGRAPHY",
+            Self::Geometry => "GEOMETRY",
+            Self::Vector => "VECTOR",
+            Self::Decfloat => "DECFLOAT",
+            Self::Map => "MAP",
+            Self::Unknown(name) => name,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
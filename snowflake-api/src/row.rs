@@ -0,0 +1,377 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::cell::{self, format_decimal};
+use crate::{CellValue, QueryResult, SnowflakeApiError};
+
+impl QueryResult {
+    /// All rows of the result, with cells decoded from Arrow batches or JSON arrays
+    pub fn rows(&self) -> Result<Vec<Row>, SnowflakeApiError> {
+        let batches = match self {
+            QueryResult::Arrow(result) => result.batches.clone(),
+            QueryResult::Json(result) => vec![result.to_record_batch()?],
+            QueryResult::Empty(_) => return Ok(vec![]),
+        };
+
+        let mut rows = vec![];
+        for batch in &batches {
+            let names: Arc<[String]> = batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .collect();
+            rows.extend(cell::batch_cells(batch)?.into_iter().map(|cells| Row {
+                names: Arc::clone(&names),
+                cells,
+            }));
+        }
+
+        Ok(rows)
+    }
+}
+
+/// Single row of the result, values are read with [`Row::get`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    names: Arc<[String]>,
+    cells: Vec<CellValue>,
+}
+
+impl Row {
+    /// Value of the column by its name or index, eg `row.get::<i64>("ID")` or `row.get::<i64>(0)`.
+    /// Column name is matched case-insensitively if there is no exact match.
+    /// Use `Option<T>` for nullable columns.
+    pub fn get<T: FromSnowflake>(&self, column: impl ColumnIndex) -> Result<T, SnowflakeApiError> {
+        let index = column.index_in(&self.names)?;
+        let cell = &self.cells[index];
+
+        T::from_cell(cell).ok_or_else(|| SnowflakeApiError::CellConversionError {
+            column: self.names[index].clone(),
+            message: if cell.is_null() {
+                "value is null, use `Option` for nullable columns".to_string()
+            } else {
+                format!(
+                    "{} value can't be read as `{}`",
+                    cell.kind(),
+                    std::any::type_name::<T>()
+                )
+            },
+        })
+    }
+
+    /// Raw cell value of the column
+    pub fn cell(&self, column: impl ColumnIndex) -> Option<&CellValue> {
+        column
+            .index_in(&self.names)
+            .ok()
+            .map(|index| &self.cells[index])
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn cells(&self) -> &[CellValue] {
+        &self.cells
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+/// Column name or index, see [`Row::get`]
+pub trait ColumnIndex {
+    fn index_in(self, names: &[String]) -> Result<usize, SnowflakeApiError>;
+}
+
+impl ColumnIndex for usize {
+    fn index_in(self, names: &[String]) -> Result<usize, SnowflakeApiError> {
+        if self < names.len() {
+            Ok(self)
+        } else {
+            Err(SnowflakeApiError::ColumnNotFound(self.to_string()))
+        }
+    }
+}
+
+impl ColumnIndex for &str {
+    fn index_in(self, names: &[String]) -> Result<usize, SnowflakeApiError> {
+        names
+            .iter()
+            .position(|name| name == self)
+            .or_else(|| {
+                names
+                    .iter()
+                    .position(|name| name.eq_ignore_ascii_case(self))
+            })
+            .ok_or_else(|| SnowflakeApiError::ColumnNotFound(self.to_string()))
+    }
+}
+
+impl ColumnIndex for String {
+    fn index_in(self, names: &[String]) -> Result<usize, SnowflakeApiError> {
+        self.as_str().index_in(names)
+    }
+}
+
+/// Conversion from the cell value, `None` if the value can't be represented by the type
+pub trait FromSnowflake: Sized {
+    fn from_cell(cell: &CellValue) -> Option<Self>;
+}
+
+impl FromSnowflake for CellValue {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        Some(cell.clone())
+    }
+}
+
+impl<T: FromSnowflake> FromSnowflake for Option<T> {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Null => Some(None),
+            cell => T::from_cell(cell).map(Some),
+        }
+    }
+}
+
+impl FromSnowflake for bool {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Boolean(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl FromSnowflake for i64 {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Int(v) => Some(*v),
+            CellValue::Decimal { value, scale: 0 } => i64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromSnowflake for i128 {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Int(v) => Some(i128::from(*v)),
+            CellValue::Decimal { value, scale: 0 } => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from_snowflake_int {
+    ($($t:ty),*) => {
+        $(
+            impl FromSnowflake for $t {
+                fn from_cell(cell: &CellValue) -> Option<Self> {
+                    i128::from_cell(cell).and_then(|v| <$t>::try_from(v).ok())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_snowflake_int!(i8, i16, i32, u8, u16, u32, u64, usize);
+
+impl FromSnowflake for f64 {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Float(v) => Some(*v),
+            #[allow(clippy::cast_precision_loss)]
+            CellValue::Int(v) => Some(*v as f64),
+            CellValue::Decimal { value, scale } => format_decimal(*value, *scale).parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromSnowflake for f32 {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        #[allow(clippy::cast_possible_truncation)]
+        f64::from_cell(cell).map(|v| v as f32)
+    }
+}
+
+impl FromSnowflake for Decimal {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Int(v) => Some(Decimal::from(*v)),
+            CellValue::Decimal { value, scale } => format_decimal(*value, *scale).parse().ok(),
+            CellValue::Float(v) => Decimal::try_from(*v).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Text as is, any other value except binary is formatted
+impl FromSnowflake for String {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Null | CellValue::Binary(_) => None,
+            CellValue::Text(v) | CellValue::Variant(Value::String(v)) => Some(v.clone()),
+            cell => Some(cell.to_string()),
+        }
+    }
+}
+
+impl FromSnowflake for Vec<u8> {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Binary(v) => Some(v.clone()),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from_snowflake_vec {
+    ($($t:ty),*) => {
+        $(
+            /// `VECTOR` or array of numbers
+            impl FromSnowflake for Vec<$t> {
+                fn from_cell(cell: &CellValue) -> Option<Self> {
+                    match cell {
+                        CellValue::Variant(v @ Value::Array(_)) => Vec::<$t>::deserialize(v).ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_snowflake_vec!(i32, i64, f32, f64);
+
+impl FromSnowflake for NaiveDate {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Date(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl FromSnowflake for NaiveTime {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Time(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl FromSnowflake for NaiveDateTime {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::TimestampNtz(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl FromSnowflake for DateTime<Utc> {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        match cell {
+            CellValue::Timestamp(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// `VARIANT` as is, other values are converted to the closest JSON type
+impl FromSnowflake for Value {
+    fn from_cell(cell: &CellValue) -> Option<Self> {
+        Some(match cell {
+            CellValue::Null => Value::Null,
+            CellValue::Boolean(v) => Value::from(*v),
+            CellValue::Int(v) => Value::from(*v),
+            CellValue::Float(v) => Value::from(*v),
+            CellValue::Variant(v) => v.clone(),
+            cell => Value::from(cell.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonResult;
+
+    #[test]
+    fn test_row_getters() {
+        let result = QueryResult::Json(JsonResult::from_rowtype(
+            serde_json::json!([
+                {"name": "ID", "type": "fixed", "precision": 18, "scale": 0, "nullable": false},
+                {"name": "PRICE", "type": "fixed", "precision": 10, "scale": 2, "nullable": true},
+                {"name": "NAME", "type": "text", "nullable": true},
+                {"name": "DAY", "type": "date", "nullable": true},
+                {"name": "DATA", "type": "binary", "nullable": true},
+                {"name": "DOC", "type": "object", "nullable": true}
+            ]),
+            serde_json::json!([["42", "9.99", null, "19675", "CAFE", "{\"a\": 1}"]]),
+        ));
+
+        let rows = result.rows().unwrap();
+        let row = &rows[0];
+        assert_eq!(row.get::<i64>("ID").unwrap(), 42);
+        assert_eq!(row.get::<u8>("id").unwrap(), 42);
+        assert_eq!(row.get::<i32>(0).unwrap(), 42);
+        assert_eq!(row.get::<Decimal>("PRICE").unwrap(), Decimal::new(999, 2));
+        assert!((row.get::<f64>("PRICE").unwrap() - 9.99).abs() < f64::EPSILON);
+        assert_eq!(row.get::<Option<String>>("NAME").unwrap(), None);
+        assert_eq!(
+            row.get::<NaiveDate>("DAY").unwrap(),
+            NaiveDate::from_ymd_opt(2023, 11, 14).unwrap()
+        );
+        assert_eq!(row.get::<Vec<u8>>("DATA").unwrap(), vec![0xCA, 0xFE]);
+        assert_eq!(
+            row.get::<Value>("DOC").unwrap(),
+            serde_json::json!({"a": 1})
+        );
+        assert_eq!(row.get_path("DOC:a").unwrap(), serde_json::json!(1));
+        assert_eq!(row.get_path("DOC:b[0]").unwrap(), Value::Null);
+
+        assert!(matches!(
+            row.get::<String>("NAME"),
+            Err(SnowflakeApiError::CellConversionError { .. })
+        ));
+        assert!(matches!(
+            row.get::<bool>("ID"),
+            Err(SnowflakeApiError::CellConversionError { .. })
+        ));
+        assert!(matches!(
+            row.get::<i64>("MISSING"),
+            Err(SnowflakeApiError::ColumnNotFound(_))
+        ));
+        assert!(row.get::<i64>(6).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_from_number_38() {
+        let result = QueryResult::Json(JsonResult::from_rowtype(
+            serde_json::json!([
+                {"name": "N", "type": "fixed", "precision": 38, "scale": 0, "nullable": false}
+            ]),
+            serde_json::json!([["18446744073709551615"], ["-1"]]),
+        ));
+
+        let rows = result.rows().unwrap();
+        assert_eq!(rows[0].get::<u64>("N").unwrap(), u64::MAX);
+        assert!(rows[0].get::<i64>("N").is_err());
+        assert!(rows[1].get::<u64>("N").is_err());
+        assert_eq!(rows[1].get::<i8>("N").unwrap(), -1);
+    }
+}
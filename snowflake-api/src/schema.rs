@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use uuid::Uuid;
+
+use crate::normalize::INT64_MAX_PRECISION;
+use crate::requests::Bindings;
+use crate::responses::{ExecResponse, SnowflakeType};
+use crate::{
+    into_query_response, variant, ExecOptions, FieldSchema, SnowflakeApi, SnowflakeApiError,
+};
+
+/// Snowflake `NUMBER` max precision, same as `Decimal128`
+const MAX_PRECISION: i64 = 38;
+/// Max scale of `TIME` sent as `Int32`
+const TIME_INT32_MAX_SCALE: i64 = 4;
+/// Max scale of `TIMESTAMP_NTZ` and `TIMESTAMP_LTZ` sent as a single scaled integer
+const SCALED_TIMESTAMP_MAX_SCALE: i64 = 7;
+/// Max scale of `TIMESTAMP_TZ` sent without the `fraction` field
+const TIMESTAMP_TZ_NO_FRACTION_MAX_SCALE: i64 = 3;
+
+impl FieldSchema {
+    /// Arrow field for the column, Snowflake type information is kept in the field metadata
+    /// under the same keys Snowflake uses in the Arrow result: `logicalType`, `scale`, `precision`,
+    /// `charLength` and `byteLength`.
+    /// `VARIANT`, `OBJECT` and `ARRAY` columns are JSON text tagged with the `arrow.json` extension type.
+    pub fn to_arrow_field(&self) -> Field {
+        let mut metadata = self.arrow_metadata();
+        if variant::is_semi_structured(self.type_.name()) {
+            variant::add_json_extension(&mut metadata);
+        }
+
+        Field::new(&self.name, self.arrow_data_type(), self.nullable).with_metadata(metadata)
+    }
+
+    /// Arrow field for the column encoded the way Snowflake sends it in the Arrow result,
+    /// so that the result without rows has the same schema as the one with rows.
+    /// [`normalize_batch`](crate::normalize_batch) turns it into [`FieldSchema::to_arrow_field`].
+    pub(crate) fn to_raw_arrow_field(&self) -> Field {
+        Field::new(&self.name, self.raw_arrow_data_type(), self.nullable)
+            .with_metadata(self.arrow_metadata())
+    }
+
+    fn arrow_metadata(&self) -> HashMap<String, String> {
+        let mut metadata =
+            HashMap::from([("logicalType".to_string(), self.type_.name().to_string())]);
+        for (key, value) in [
+            ("scale", self.scale),
+            ("precision", self.precision),
+            ("charLength", self.length),
+            ("byteLength", self.byte_length),
+        ] {
+            if let Some(value) = value {
+                metadata.insert(key.to_string(), value.to_string());
+            }
+        }
+        metadata
+    }
+
+    /// Integer width is derived the way the server does it: from the precision for `FIXED`,
+    /// which is `Decimal128` if it doesn't fit into `Int64`, and from the scale for `TIME`
+    fn raw_arrow_data_type(&self) -> DataType {
+        let scale = self.scale.unwrap_or(0);
+        let field = |name: &str, data_type| Field::new(name, data_type, false);
+        match self.type_ {
+            SnowflakeType::Fixed => match self.precision.unwrap_or(MAX_PRECISION) {
+                precision if precision <= 2 => DataType::Int8,
+                precision if precision <= 4 => DataType::Int16,
+                precision if precision <= 9 => DataType::Int32,
+                precision if precision <= i64::from(INT64_MAX_PRECISION) => DataType::Int64,
+                _ => self.arrow_data_type(),
+            },
+            SnowflakeType::Time if scale <= TIME_INT32_MAX_SCALE => DataType::Int32,
+            SnowflakeType::Time => DataType::Int64,
+            SnowflakeType::TimestampNtz | SnowflakeType::TimestampLtz
+                if scale <= SCALED_TIMESTAMP_MAX_SCALE =>
+            {
+                DataType::Int64
+            }
+            SnowflakeType::TimestampNtz | SnowflakeType::TimestampLtz => {
+                DataType::Struct(Fields::from(vec![
+                    field("epoch", DataType::Int64),
+                    field("fraction", DataType::Int32),
+                ]))
+            }
+            SnowflakeType::TimestampTz if scale <= TIMESTAMP_TZ_NO_FRACTION_MAX_SCALE => {
+                DataType::Struct(Fields::from(vec![
+                    field("epoch", DataType::Int64),
+                    field("timezone", DataType::Int32),
+                ]))
+            }
+            SnowflakeType::TimestampTz => DataType::Struct(Fields::from(vec![
+                field("epoch", DataType::Int64),
+                field("fraction", DataType::Int32),
+                field("timezone", DataType::Int32),
+            ])),
+            SnowflakeType::Decfloat => DataType::Struct(Fields::from(vec![
+                field("exponent", DataType::Int16),
+                field("significand", DataType::Binary),
+            ])),
+            _ => self.arrow_data_type(),
+        }
+    }
+
+    fn arrow_data_type(&self) -> DataType {
+        match self.type_ {
+            SnowflakeType::Fixed => {
+                let scale = self.scale.unwrap_or(0);
+                let precision = self.precision.unwrap_or(MAX_PRECISION);
+                if scale == 0 && precision <= i64::from(INT64_MAX_PRECISION) {
+                    DataType::Int64
+                } else {
+                    // both are bounded by 38
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    DataType::Decimal128(precision as u8, scale as i8)
+                }
+            }
+            SnowflakeType::Real => DataType::Float64,
+            // spatial types are GeoJSON, WKT or hex WKB text, depending on the output format,
+            // `DECFLOAT` doesn't fit into `Decimal128`
+            SnowflakeType::Text
+            | SnowflakeType::Variant
+            | SnowflakeType::Object
+            | SnowflakeType::Array
+            | SnowflakeType::Map
+            | SnowflakeType::Geography
+            | SnowflakeType::Geometry
+            | SnowflakeType::Decfloat
+            | SnowflakeType::Unknown(_) => DataType::Utf8,
+            SnowflakeType::Vector => {
+                let item = Field::new_list_field(self.vector_element_type(), false);
+                match self.vector_dimension.and_then(|d| i32::try_from(d).ok()) {
+                    Some(dimension) => DataType::FixedSizeList(Arc::new(item), dimension),
+                    None => DataType::List(Arc::new(item)),
+                }
+            }
+            SnowflakeType::Date => DataType::Date32,
+            SnowflakeType::Time => DataType::Time64(TimeUnit::Nanosecond),
+            SnowflakeType::TimestampNtz => DataType::Timestamp(TimeUnit::Nanosecond, None),
+            SnowflakeType::TimestampLtz | SnowflakeType::TimestampTz => {
+                DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()))
+            }
+            SnowflakeType::Boolean => DataType::Boolean,
+            SnowflakeType::Binary => DataType::Binary,
+        }
+    }
+
+    /// `VECTOR(INT, n)` elements are 32-bit integers, `VECTOR(FLOAT, n)` are 32-bit floats
+    fn vector_element_type(&self) -> DataType {
+        match self.element_type {
+            Some(SnowflakeType::Fixed) => DataType::Int32,
+            _ => DataType::Float32,
+        }
+    }
+}
+
+/// Arrow schema of the result, field order matches the column order
+pub(crate) fn arrow_schema(fields: &[FieldSchema]) -> SchemaRef {
+    Arc::new(Schema::new(
+        fields
+            .iter()
+            .map(FieldSchema::to_arrow_field)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Arrow schema of the result as Snowflake sends it, see [`FieldSchema::to_raw_arrow_field`]
+pub(crate) fn raw_arrow_schema(fields: &[FieldSchema]) -> SchemaRef {
+    Arc::new(Schema::new(
+        fields
+            .iter()
+            .map(FieldSchema::to_raw_arrow_field)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+impl SnowflakeApi {
+    /// Schema of the query result, query is only compiled and never executed.
+    /// Statements which don't return result set get an empty schema.
+    pub async fn describe(&self, sql: &str) -> Result<SchemaRef, SnowflakeApiError> {
+        let options = ExecOptions::new().with_describe_only(true);
+        let request_id = Uuid::new_v4();
+        let resp = self
+            .abort_on_drop(request_id, async {
+                let resp = self
+                    .run_sql::<ExecResponse>(sql, Bindings::None, false, &options, request_id)
+                    .await?;
+                log::debug!("Got describe response: {resp:?}");
+
+                self.wait_for_result(into_query_response(resp)?).await
+            })
+            .await?;
+        let fields: Vec<FieldSchema> = resp
+            .data
+            .as_sync()?
+            .rowtype
+            .unwrap_or_default()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(arrow_schema(&fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::responses::{ExecResponseRowType, QueryContext};
+    use crate::{normalize_batch, BytesResult, QueryResult, RawQueryResult};
+
+    #[test]
+    fn test_rowtype_to_arrow_schema() {
+        let rowtype: Vec<ExecResponseRowType> = serde_json::from_value(serde_json::json!([
+            {"name": "ID", "type": "fixed", "precision": 18, "scale": 0, "nullable": false},
+            {"name": "PRICE", "type": "fixed", "precision": 10, "scale": 2, "nullable": true},
+            {"name": "NAME", "type": "text", "length": 16, "byteLength": 64, "nullable": true},
+            {"name": "CREATED", "type": "timestamp_tz", "precision": 0, "scale": 9, "nullable": true},
+            {"name": "PAYLOAD", "type": "variant", "nullable": true}
+        ]))
+        .unwrap();
+        let fields: Vec<FieldSchema> = rowtype.into_iter().map(Into::into).collect();
+        let schema = arrow_schema(&fields);
+
+        let types: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|f| f.data_type().clone())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                DataType::Int64,
+                DataType::Decimal128(10, 2),
+                DataType::Utf8,
+                DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+                DataType::Utf8,
+            ]
+        );
+        assert!(!schema.field(0).is_nullable());
+
+        let name = schema.field(2).metadata();
+        assert_eq!(name["logicalType"], "TEXT");
+        assert_eq!(name["charLength"], "16");
+        assert_eq!(name["byteLength"], "64");
+        assert_eq!(schema.field(1).metadata()["scale"], "2");
+        assert_eq!(
+            schema.field(4).metadata()["ARROW:extension:name"],
+            "arrow.json"
+        );
+    }
+
+    fn bytes_result(chunks: Vec<bytes::Bytes>, schema: Option<SchemaRef>) -> RecordBatch {
+        let raw = RawQueryResult::Bytes(BytesResult {
+            chunks,
+            schema,
+            query_id: String::new(),
+            statement_type_id: None,
+            send_result_time: 0,
+            query_context: QueryContext { entries: vec![] },
+        });
+        let QueryResult::Arrow(mut result) = raw.deserialize_arrow().unwrap() else {
+            panic!("expected arrow result");
+        };
+        result.batches.remove(0)
+    }
+
+    fn data_types(batch: &RecordBatch) -> Vec<DataType> {
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.data_type().clone())
+            .collect()
+    }
+
+    /// Zero-row result of the same columns as the Snowflake payload has the same types, raw and normalized
+    fn assert_empty_result_matches(rowtype: serde_json::Value, payload: &'static [u8]) {
+        let rowtype: Vec<ExecResponseRowType> = serde_json::from_value(rowtype).unwrap();
+        let fields: Vec<FieldSchema> = rowtype.into_iter().map(Into::into).collect();
+        let empty = bytes_result(vec![], Some(raw_arrow_schema(&fields)));
+        let non_empty = bytes_result(vec![bytes::Bytes::from_static(payload)], None);
+
+        assert_eq!(empty.num_rows(), 0);
+        assert_eq!(data_types(&empty), data_types(&non_empty));
+        assert_eq!(
+            data_types(&normalize_batch(&empty).unwrap()),
+            data_types(&normalize_batch(&non_empty).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_empty_fixed_result_matches_payload() {
+        assert_empty_result_matches(
+            serde_json::json!([
+                {"name": "PRICE", "type": "fixed", "precision": 10, "scale": 2, "nullable": true},
+                {"name": "QTY", "type": "fixed", "precision": 4, "scale": 0, "nullable": true},
+                {"name": "ID", "type": "fixed", "precision": 18, "scale": 0, "nullable": true},
+                {"name": "TOTAL", "type": "fixed", "precision": 38, "scale": 0, "nullable": true}
+            ]),
+            include_bytes!("../tests/fixtures/fixed.arrow"),
+        );
+    }
+
+    #[test]
+    fn test_empty_time_result_matches_payload() {
+        assert_empty_result_matches(
+            serde_json::json!([
+                {"name": "AT_MS", "type": "time", "scale": 3, "nullable": true},
+                {"name": "AT_NS", "type": "time", "scale": 9, "nullable": true}
+            ]),
+            include_bytes!("../tests/fixtures/time.arrow"),
+        );
+    }
+
+    #[test]
+    fn test_empty_timestamp_result_matches_payload() {
+        for (type_, payload) in [
+            (
+                "timestamp_ntz",
+                include_bytes!("../tests/fixtures/timestamp_ntz.arrow").as_slice(),
+            ),
+            (
+                "timestamp_ltz",
+                include_bytes!("../tests/fixtures/timestamp_ltz.arrow").as_slice(),
+            ),
+            (
+                "timestamp_tz",
+                include_bytes!("../tests/fixtures/timestamp_tz.arrow").as_slice(),
+            ),
+        ] {
+            assert_empty_result_matches(
+                serde_json::json!([
+                    {"name": "TS3", "type": type_, "scale": 3, "nullable": true},
+                    {"name": "TS9", "type": type_, "scale": 9, "nullable": true}
+                ]),
+                payload,
+            );
+        }
+    }
+}
@@ -1,22 +1,50 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use futures::future::BoxFuture;
 use futures::lock::Mutex;
 #[cfg(feature = "cert-auth")]
 use snowflake_jwt::generate_jwt_token;
 use thiserror::Error;
 
+use crate::browser::{self, SsoListener, BROWSER_LOGIN_TIMEOUT};
 use crate::connection;
 use crate::connection::{Connection, QueryType};
-#[cfg(feature = "cert-auth")]
-use crate::requests::{CertLoginRequest, CertRequestData};
 use crate::requests::{
-    ClientEnvironment, LoginRequest, LoginRequestCommon, PasswordLoginRequest, PasswordRequestData,
-    RenewSessionRequest, SessionParameters,
+    AuthenticatorRequest, AuthenticatorRequestData, ClientEnvironment, ExternalBrowserLoginRequest,
+    ExternalBrowserRequestData, LoginRequest, LoginRequestCommon, OAuthLoginRequest,
+    OAuthRequestData, PasswordLoginRequest, PasswordRequestData, RenewSessionRequest,
+    SessionParameters,
 };
-use crate::responses::AuthResponse;
+#[cfg(feature = "cert-auth")]
+use crate::requests::{CertLoginRequest, CertRequestData};
+use crate::responses::{AuthResponse, AuthenticatorResponseData, BaseRestResponse};
 use crate::utils::parse_account;
 
+/// Session token has expired, it could be renewed with the master token
+pub(crate) const SESSION_EXPIRED: &str = "390112";
+/// Master token has expired, new session has to be started
+pub(crate) const MASTER_TOKEN_EXPIRED: &str = "390114";
+pub(crate) const INVALID_TOKEN: &str = "390104";
+/// OAuth access token used to log in has expired
+pub(crate) const OAUTH_TOKEN_EXPIRED: &str = "390318";
+
+/// Returns a fresh OAuth access token, called when the server reports the current one has expired
+pub type OAuthTokenRefresh = Arc<
+    dyn Fn() -> BoxFuture<'static, Result<String, Box<dyn std::error::Error + Send + Sync>>>
+        + Send
+        + Sync,
+>;
+
+/// Request was rejected because of the session token, not the request itself
+pub(crate) fn is_session_error(code: &str) -> bool {
+    matches!(
+        code,
+        SESSION_EXPIRED | MASTER_TOKEN_EXPIRED | INVALID_TOKEN | OAUTH_TOKEN_EXPIRED
+    )
+}
+
 #[derive(Error, Debug)]
 pub enum AuthError {
     #[error(transparent)]
@@ -35,6 +63,18 @@ pub enum AuthError {
     #[error("Certificate auth was requested, but certificate wasn't provided")]
     MissingCertificate,
 
+    #[error("OAuth auth was requested, but token wasn't provided")]
+    MissingOAuthToken,
+
+    #[error("Failed to refresh OAuth token")]
+    OAuthRefreshFailed(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Failed to receive SSO token from the browser: {0}")]
+    SsoListenerError(#[from] std::io::Error),
+
+    #[error("Browser login wasn't completed in time")]
+    BrowserLoginTimeout,
+
     #[error("Unexpected API response")]
     UnexpectedResponse,
 
@@ -98,6 +138,12 @@ impl AuthToken {
         Instant::now().duration_since(self.issued_on) >= self.valid_for
     }
 
+    pub fn expires_within(&self, period: Duration) -> bool {
+        self.valid_for
+            .saturating_sub(Instant::now().duration_since(self.issued_on))
+            <= period
+    }
+
     pub fn auth_header(&self) -> String {
         format!("Snowflake Token=\"{}\"", &self.token)
     }
@@ -106,13 +152,16 @@ impl AuthToken {
 enum AuthType {
     Certificate,
     Password,
+    OAuth,
+    ExternalBrowser,
 }
 
 /// Requests, caches, and renews authentication tokens.
 /// Tokens are given as response to creating new session in Snowflake. Session persists
 /// the configuration state and temporary objects (tables, procedures, etc).
+/// Session is closed on drop in the background, use [`Session::close`] to wait for it.
+/// If dropped outside of tokio runtime, session is left open until it expires.
 // todo: split warehouse-database-schema and username-role-key into its own structs
-// todo: close session after object is dropped
 pub struct Session {
     connection: Arc<Connection>,
 
@@ -130,6 +179,12 @@ pub struct Session {
     #[allow(dead_code)]
     private_key_pem: Option<String>,
     password: Option<String>,
+    oauth_token: Mutex<Option<String>>,
+    oauth_refresh: Option<OAuthTokenRefresh>,
+    /// Replaced in tests, where the SSO callback is sent instead of launching the browser
+    open_browser: fn(&str),
+
+    keep_alive: AtomicBool,
 }
 
 // todo: make builder
@@ -169,6 +224,10 @@ impl Session {
             role,
             schema,
             password: None,
+            oauth_token: Mutex::new(None),
+            oauth_refresh: None,
+            open_browser: browser::open_browser,
+            keep_alive: AtomicBool::new(false),
         }
     }
 
@@ -206,9 +265,106 @@ impl Session {
             password,
             schema,
             private_key_pem: None,
+            oauth_token: Mutex::new(None),
+            oauth_refresh: None,
+            open_browser: browser::open_browser,
+            keep_alive: AtomicBool::new(false),
+        }
+    }
+
+    /// Authenticate using OAuth access token issued by the identity provider
+    // fixme: add builder or introduce structs
+    #[allow(clippy::too_many_arguments)]
+    pub fn oauth_auth(
+        connection: Arc<Connection>,
+        account_identifier: &str,
+        warehouse: Option<&str>,
+        database: Option<&str>,
+        schema: Option<&str>,
+        username: &str,
+        role: Option<&str>,
+        token: &str,
+    ) -> Self {
+        let account_identifier = account_identifier.to_uppercase();
+
+        let database = database.map(str::to_uppercase);
+        let schema = schema.map(str::to_uppercase);
+
+        let username = username.to_uppercase();
+        let role = role.map(str::to_uppercase);
+
+        Self {
+            connection,
+            auth_tokens: Mutex::new(None),
+            auth_type: AuthType::OAuth,
+            account_identifier,
+            warehouse: warehouse.map(str::to_uppercase),
+            database,
+            username,
+            role,
+            schema,
+            password: None,
+            private_key_pem: None,
+            oauth_token: Mutex::new(Some(token.to_string())),
+            oauth_refresh: None,
+            open_browser: browser::open_browser,
+            keep_alive: AtomicBool::new(false),
         }
     }
 
+    /// Authenticate through the identity provider in the web browser, using SAML 2.0 SSO.
+    /// Browser is opened on login, which happens again when the session can not be renewed.
+    pub fn external_browser_auth(
+        connection: Arc<Connection>,
+        account_identifier: &str,
+        warehouse: Option<&str>,
+        database: Option<&str>,
+        schema: Option<&str>,
+        username: &str,
+        role: Option<&str>,
+    ) -> Self {
+        let account_identifier = account_identifier.to_uppercase();
+
+        let database = database.map(str::to_uppercase);
+        let schema = schema.map(str::to_uppercase);
+
+        let username = username.to_uppercase();
+        let role = role.map(str::to_uppercase);
+
+        Self {
+            connection,
+            auth_tokens: Mutex::new(None),
+            auth_type: AuthType::ExternalBrowser,
+            account_identifier,
+            warehouse: warehouse.map(str::to_uppercase),
+            database,
+            username,
+            role,
+            schema,
+            password: None,
+            private_key_pem: None,
+            oauth_token: Mutex::new(None),
+            oauth_refresh: None,
+            open_browser: browser::open_browser,
+            keep_alive: AtomicBool::new(false),
+        }
+    }
+
+    /// Callback providing a new OAuth access token when the server rejects the current one as expired
+    #[must_use]
+    pub fn with_oauth_refresh(mut self, refresh: OAuthTokenRefresh) -> Self {
+        self.oauth_refresh = Some(refresh);
+        self
+    }
+
+    /// Ask server to keep the session alive while the client sends heartbeats,
+    /// see [`SnowflakeApiBuilder::with_session_keep_alive`](crate::SnowflakeApiBuilder::with_session_keep_alive)
+    #[must_use]
+    pub fn with_keep_alive(mut self, enabled: bool) -> Self {
+        *self.keep_alive.get_mut() = enabled;
+        self
+    }
+
     /// Get cached token or request a new one if old one has expired.
     pub async fn get_token(&self) -> Result<AuthParts, AuthError> {
         let mut auth_tokens = self.auth_tokens.lock().await;
@@ -218,28 +374,13 @@ impl Session {
                 .is_some_and(|at| at.master_token.is_expired())
         {
             // Create new session if tokens are absent or can not be exchange
-            let tokens = match self.auth_type {
-                AuthType::Certificate => {
-                    log::info!("Starting session with certificate authentication");
-                    if cfg!(feature = "cert-auth") {
-                        self.create(self.cert_request_body()?).await
-                    } else {
-                        Err(AuthError::MissingCertificate)?
-                    }
-                }
-                AuthType::Password => {
-                    log::info!("Starting session with password authentication");
-                    self.create(self.passwd_request_body()?).await
-                }
-            }?;
-            *auth_tokens = Some(tokens);
+            *auth_tokens = Some(self.login().await?);
         } else if auth_tokens
             .as_ref()
             .is_some_and(|at| at.session_token.is_expired())
         {
             // Renew old session token
-            let old_token = auth_tokens.take().unwrap();
-            let tokens = self.renew(old_token).await?;
+            let tokens = self.renew(auth_tokens.as_ref().unwrap()).await?;
             *auth_tokens = Some(tokens);
         }
         auth_tokens.as_mut().unwrap().sequence_id += 1;
@@ -249,35 +390,192 @@ impl Session {
         })
     }
 
-    pub async fn close(&mut self) -> Result<(), AuthError> {
-        if let Some(tokens) = self.auth_tokens.lock().await.take() {
-            log::debug!("Closing sessions");
-
-            let resp = self
-                .connection
-                .request::<AuthResponse>(
-                    QueryType::CloseSession,
-                    &self.account_identifier,
-                    &[("delete", "true")],
-                    Some(&tokens.session_token.auth_header()),
-                    serde_json::Value::default(),
-                    None,
-                )
-                .await?;
-
-            match resp {
-                AuthResponse::Close(_) => Ok(()),
-                AuthResponse::Error(e) => Err(AuthError::AuthFailed(
-                    e.code.unwrap_or_default(),
-                    e.message.unwrap_or_default(),
-                )),
-                _ => Err(AuthError::UnexpectedResponse),
+    /// Replace the tokens server has rejected with `code`: session token is renewed if it has expired,
+    /// otherwise new session is started.
+    /// Tokens which were already replaced by a concurrent request are kept as is.
+    pub(crate) async fn refresh(&self, rejected: &AuthParts, code: &str) -> Result<(), AuthError> {
+        let mut auth_tokens = self.auth_tokens.lock().await;
+        let Some(tokens) = auth_tokens.take() else {
+            return Ok(());
+        };
+        if tokens.session_token.auth_header() != rejected.session_token_auth_header {
+            *auth_tokens = Some(tokens);
+            return Ok(());
+        }
+
+        let renewed = if code == SESSION_EXPIRED {
+            self.renew(&tokens)
+                .await
+                .inspect_err(|e| log::info!("Failed to renew the session, logging in again: {e}"))
+                .ok()
+        } else {
+            None
+        };
+        let tokens = match renewed {
+            Some(tokens) => tokens,
+            None => self.login().await?,
+        };
+        *auth_tokens = Some(tokens);
+        Ok(())
+    }
+
+    /// Tell server the session is still in use, renewing the session token if it would expire
+    /// before the next heartbeat. Does nothing if there is no session yet or it was closed.
+    pub(crate) async fn heartbeat(&self, interval: Duration) -> Result<(), AuthError> {
+        let auth = {
+            let mut auth_tokens = self.auth_tokens.lock().await;
+            let Some(tokens) = auth_tokens.as_mut() else {
+                return Ok(());
+            };
+            // tokens are only replaced once renewed, failed renewal doesn't lose the session
+            if tokens.session_token.expires_within(interval) {
+                *tokens = self.renew(tokens).await?;
             }
+            tokens.session_token.auth_header()
+        };
+
+        log::debug!("Sending session heartbeat");
+        let resp = self
+            .connection
+            .request::<BaseRestResponse<serde_json::Value>>(
+                QueryType::Heartbeat,
+                &self.account_identifier,
+                &[],
+                Some(&auth),
+                serde_json::Value::default(),
+                None,
+            )
+            .await?;
+
+        if resp.success {
+            Ok(())
+        } else {
+            Err(AuthError::AuthFailed(
+                resp.code.unwrap_or_default(),
+                resp.message.unwrap_or_default(),
+            ))
+        }
+    }
+
+    /// Close the session, next request starts a new one, which isn't kept alive
+    pub async fn close(&self) -> Result<(), AuthError> {
+        self.keep_alive.store(false, Ordering::Relaxed);
+        if let Some(tokens) = self.auth_tokens.lock().await.take() {
+            close_session(&self.connection, &self.account_identifier, &tokens).await
         } else {
             Ok(())
         }
     }
 
+    async fn login(&self) -> Result<AuthTokens, AuthError> {
+        match self.auth_type {
+            AuthType::Certificate => {
+                log::info!("Starting session with certificate authentication");
+                if cfg!(feature = "cert-auth") {
+                    self.create(self.cert_request_body()?).await
+                } else {
+                    Err(AuthError::MissingCertificate)
+                }
+            }
+            AuthType::Password => {
+                log::info!("Starting session with password authentication");
+                self.create(self.passwd_request_body()?).await
+            }
+            AuthType::OAuth => {
+                log::info!("Starting session with OAuth authentication");
+                self.oauth_login().await
+            }
+            AuthType::ExternalBrowser => {
+                log::info!("Starting session with external browser authentication");
+                self.external_browser_login().await
+            }
+        }
+    }
+
+    /// Open SSO URL in the browser and log in with the SAML token the identity provider
+    /// redirects the browser to the local listener with
+    async fn external_browser_login(&self) -> Result<AuthTokens, AuthError> {
+        let listener = SsoListener::bind().await?;
+        let sso = self.authenticator_request(listener.port()?).await?;
+
+        (self.open_browser)(&sso.sso_url);
+        let token = tokio::time::timeout(BROWSER_LOGIN_TIMEOUT, listener.receive_token())
+            .await
+            .map_err(|_| AuthError::BrowserLoginTimeout)??;
+
+        self.create(ExternalBrowserLoginRequest {
+            data: ExternalBrowserRequestData {
+                login_request_common: self.login_request_common(),
+                authenticator: "EXTERNALBROWSER".to_string(),
+                token,
+                proof_key: sso.proof_key,
+            },
+        })
+        .await
+    }
+
+    async fn authenticator_request(
+        &self,
+        redirect_port: u16,
+    ) -> Result<AuthenticatorResponseData, AuthError> {
+        let body = AuthenticatorRequest {
+            data: AuthenticatorRequestData {
+                login_request_common: self.login_request_common(),
+                authenticator: "EXTERNALBROWSER".to_string(),
+                browser_mode_redirect_port: redirect_port.to_string(),
+            },
+        };
+
+        let resp = self
+            .connection
+            .request::<AuthResponse>(
+                QueryType::AuthenticatorRequest,
+                &self.account_identifier,
+                &[],
+                None,
+                body,
+                None,
+            )
+            .await?;
+
+        match resp {
+            AuthResponse::Auth(ar) => Ok(ar.data),
+            AuthResponse::Error(e) => Err(AuthError::AuthFailed(
+                e.code.unwrap_or_default(),
+                e.message.unwrap_or_default(),
+            )),
+            _ => Err(AuthError::UnexpectedResponse),
+        }
+    }
+
+    /// Log in with the current OAuth token, refreshing it once if the server reports it expired
+    async fn oauth_login(&self) -> Result<AuthTokens, AuthError> {
+        let mut token = self.oauth_token.lock().await;
+        let current = token.clone().ok_or(AuthError::MissingOAuthToken)?;
+        match self.create(self.oauth_request_body(current)).await {
+            Err(AuthError::AuthFailed(code, message)) if code == OAUTH_TOKEN_EXPIRED => {
+                let Some(refresh) = &self.oauth_refresh else {
+                    return Err(AuthError::AuthFailed(code, message));
+                };
+                log::info!("OAuth token has expired, refreshing it");
+                let refreshed = refresh().await.map_err(AuthError::OAuthRefreshFailed)?;
+                *token = Some(refreshed.clone());
+                self.create(self.oauth_request_body(refreshed)).await
+            }
+            result => result,
+        }
+    }
+
+    fn oauth_request_body(&self, token: String) -> OAuthLoginRequest {
+        OAuthLoginRequest {
+            data: OAuthRequestData {
+                login_request_common: self.login_request_common(),
+                authenticator: "OAUTH".to_string(),
+                token,
+            },
+        }
+    }
+
     #[cfg(feature = "cert-auth")]
     fn cert_request_body(&self) -> Result<CertLoginRequest, AuthError> {
         let account_name = parse_account(&self.account_identifier);
@@ -373,6 +671,7 @@ impl Session {
             login_name: self.username.clone(),
             session_parameters: SessionParameters {
                 client_validate_default_parameters: true,
+                client_session_keep_alive: self.keep_alive.load(Ordering::Relaxed),
             },
             client_environment: ClientEnvironment {
                 application: "Rust".to_string(),
@@ -384,7 +683,7 @@ impl Session {
         }
     }
 
-    async fn renew(&self, token: AuthTokens) -> Result<AuthTokens, AuthError> {
+    async fn renew(&self, token: &AuthTokens) -> Result<AuthTokens, AuthError> {
         log::debug!("Renewing the token");
         let auth = token.master_token.auth_header();
         let body = RenewSessionRequest {
@@ -425,3 +724,431 @@ impl Session {
         }
     }
 }
+
+async fn close_session(
+    connection: &Connection,
+    account_identifier: &str,
+    tokens: &AuthTokens,
+) -> Result<(), AuthError> {
+    log::debug!("Closing sessions");
+
+    let resp = connection
+        .request::<AuthResponse>(
+            QueryType::CloseSession,
+            account_identifier,
+            &[("delete", "true")],
+            Some(&tokens.session_token.auth_header()),
+            serde_json::Value::default(),
+            None,
+        )
+        .await?;
+
+    match resp {
+        AuthResponse::Close(_) => Ok(()),
+        AuthResponse::Error(e) => Err(AuthError::AuthFailed(
+            e.code.unwrap_or_default(),
+            e.message.unwrap_or_default(),
+        )),
+        _ => Err(AuthError::UnexpectedResponse),
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let Some(tokens) = self.auth_tokens.get_mut().take() else {
+            return;
+        };
+        if tokens.session_token.is_expired() {
+            return;
+        }
+
+        let connection = Arc::clone(&self.connection);
+        let account_identifier = self.account_identifier.clone();
+        let close = async move {
+            if let Err(e) = close_session(&connection, &account_identifier, &tokens).await {
+                log::warn!("Failed to close the session on drop: {e}");
+            }
+        };
+
+        // client belongs to the runtime the session was used in, so it's not closed outside of it
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(close);
+            }
+            Err(_) => log::warn!(
+                "Session dropped outside of tokio runtime is left open until it expires, \
+                use `SnowflakeApi::shutdown` to close it"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+    use wiremock::{Mock, MockBuilder, MockServer, Request, Respond, ResponseTemplate};
+
+    use super::*;
+    use crate::test_utils::{api, connection, login_response, success_response};
+    use crate::{QueryResult, SnowflakeApiError};
+
+    fn error_response(code: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "code": code,
+            "message": "Authentication token has expired.  The user must authenticate again.",
+            "success": false,
+            "data": null
+        }))
+    }
+
+    fn query_response() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "code": null,
+            "message": null,
+            "success": true,
+            "data": {
+                "rowtype": [{"name": "ONE", "type": "fixed", "precision": 1, "scale": 0, "nullable": false}],
+                "rowset": [["1"]],
+                "total": 1,
+                "returned": 1,
+                "queryId": "01b2c3d4-0000-0001-0000-000000000001",
+                "statementTypeId": 4096,
+                "sendResultTime": 0,
+                "queryContext": {"entries": []}
+            }
+        }))
+    }
+
+    fn query_request(token: &str) -> MockBuilder {
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .and(header(
+                "authorization",
+                format!("Snowflake Token=\"{token}\""),
+            ))
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_is_renewed_and_request_replayed() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/v1/login-request"))
+            .respond_with(login_response("session-1"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/session/token-request"))
+            .and(header("authorization", "Snowflake Token=\"master\""))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": null,
+                "message": null,
+                "success": true,
+                "data": {
+                    "sessionToken": "session-2",
+                    "validityInSecondsST": 3600,
+                    "masterToken": "master",
+                    "validityInSecondsMT": 14400,
+                    "sessionId": 1
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        query_request("session-1")
+            .respond_with(error_response(SESSION_EXPIRED))
+            .expect(1)
+            .mount(&server)
+            .await;
+        query_request("session-2")
+            .respond_with(query_response())
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = api(&server).exec("SELECT 1").await.unwrap().data;
+        assert!(matches!(result, QueryResult::Json(_)));
+        assert_eq!(result.rows().unwrap()[0].get::<i64>("ONE").unwrap(), 1);
+
+        let request_ids: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.url.path() == "/queries/v1/query-request")
+            .filter_map(|r| {
+                r.url
+                    .query_pairs()
+                    .find(|(k, _)| k == "requestId")
+                    .map(|(_, v)| v.to_string())
+            })
+            .collect();
+        assert_eq!(request_ids.len(), 2);
+        assert_eq!(request_ids[0], request_ids[1]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_token_is_replayed_only_once() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/v1/login-request"))
+            .respond_with(login_response("session"))
+            .expect(2)
+            .mount(&server)
+            .await;
+        query_request("session")
+            .respond_with(error_response(INVALID_TOKEN))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let err = api(&server).exec("SELECT 1").await.unwrap_err();
+        assert!(
+            matches!(err, SnowflakeApiError::ApiError { ref code, .. } if code == INVALID_TOKEN),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_oauth_token_is_refreshed() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/v1/login-request"))
+            .and(body_partial_json(
+                json!({"data": {"AUTHENTICATOR": "OAUTH", "TOKEN": "expired"}}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": OAUTH_TOKEN_EXPIRED,
+                "message": "OAuth access token expired.",
+                "success": false,
+                "data": {"authnMethod": "OAUTH", "errorCode": OAUTH_TOKEN_EXPIRED}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/session/v1/login-request"))
+            .and(body_partial_json(
+                json!({"data": {"AUTHENTICATOR": "OAUTH", "TOKEN": "fresh"}}),
+            ))
+            .respond_with(login_response("session"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let refreshed = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&refreshed);
+        let connection = connection(&server);
+        let session = Session::oauth_auth(
+            connection, "test", None, None, None, "user", None, "expired",
+        )
+        .with_oauth_refresh(Arc::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Ok("fresh".to_string()) })
+        }));
+
+        let parts = session.get_token().await.unwrap();
+        assert_eq!(
+            parts.session_token_auth_header,
+            "Snowflake Token=\"session\""
+        );
+        assert_eq!(refreshed.load(Ordering::SeqCst), 1);
+        assert_eq!(session.oauth_token.lock().await.as_deref(), Some("fresh"));
+    }
+
+    /// Identity provider redirecting the browser straight back to the port of the SSO listener
+    struct SsoUrlResponder;
+
+    impl Respond for SsoUrlResponder {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: serde_json::Value = request.body_json().unwrap();
+            let port = body["data"]["BROWSER_MODE_REDIRECT_PORT"].as_str().unwrap();
+            success_response(&json!({
+                "ssoUrl": format!("http://127.0.0.1:{port}/?token=saml%2Btoken"),
+                "proofKey": "proof"
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_external_browser_login() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/authenticator-request"))
+            .and(body_partial_json(
+                json!({"data": {"AUTHENTICATOR": "EXTERNALBROWSER", "LOGIN_NAME": "USER"}}),
+            ))
+            .respond_with(SsoUrlResponder)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/session/v1/login-request"))
+            .and(body_partial_json(json!({"data": {
+                "AUTHENTICATOR": "EXTERNALBROWSER",
+                "TOKEN": "saml+token",
+                "PROOF_KEY": "proof"
+            }})))
+            .respond_with(login_response("session"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut session = Session::external_browser_auth(
+            connection(&server),
+            "test",
+            None,
+            None,
+            None,
+            "user",
+            None,
+        );
+        session.open_browser = |url| {
+            let url = url.to_string();
+            tokio::spawn(async move {
+                let page = reqwest::get(url).await.unwrap();
+                assert_eq!(page.status(), 200);
+            });
+        };
+
+        let parts = session.get_token().await.unwrap();
+        assert_eq!(
+            parts.session_token_auth_header,
+            "Snowflake Token=\"session\""
+        );
+    }
+
+    fn close_request() -> MockBuilder {
+        Mock::given(method("POST"))
+            .and(path("/session"))
+            .and(query_param("delete", "true"))
+            .and(header("authorization", "Snowflake Token=\"session\""))
+    }
+
+    fn close_response() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "code": null,
+            "message": null,
+            "success": true,
+            "data": null
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_session_closed_on_drop() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/v1/login-request"))
+            .respond_with(login_response("session"))
+            .mount(&server)
+            .await;
+        query_request("session")
+            .respond_with(query_response())
+            .mount(&server)
+            .await;
+        close_request()
+            .respond_with(close_response())
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = api(&server);
+        api.exec("SELECT 1").await.unwrap();
+        drop(api);
+
+        for _ in 0..50 {
+            let requests = server.received_requests().await.unwrap();
+            if requests.iter().any(|r| r.url.path() == "/session") {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("session wasn't closed");
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_closes_session_once() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/v1/login-request"))
+            .respond_with(login_response("session"))
+            .mount(&server)
+            .await;
+        query_request("session")
+            .respond_with(query_response())
+            .mount(&server)
+            .await;
+        close_request()
+            .respond_with(close_response())
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = api(&server);
+        api.exec("SELECT 1").await.unwrap();
+        api.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_heartbeat_renewal_keeps_session() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/v1/login-request"))
+            .respond_with(login_response("session"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/session/token-request"))
+            .respond_with(error_response(SESSION_EXPIRED))
+            .expect(1)
+            .mount(&server)
+            .await;
+        query_request("session")
+            .respond_with(query_response())
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let api = api(&server);
+        api.exec("SELECT 1").await.unwrap();
+        // session token would expire before the next heartbeat, so it's renewed
+        assert!(api
+            .session
+            .heartbeat(Duration::from_secs(4000))
+            .await
+            .is_err());
+        api.exec("SELECT 1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_closed_session_is_not_kept_alive() {
+        let server = MockServer::start().await;
+        Mock::given(path("/session/v1/login-request"))
+            .respond_with(login_response("session"))
+            .expect(2)
+            .mount(&server)
+            .await;
+        query_request("session")
+            .respond_with(query_response())
+            .mount(&server)
+            .await;
+        close_request()
+            .respond_with(close_response())
+            .mount(&server)
+            .await;
+
+        let connection = connection(&server);
+        let session = crate::test_utils::session(Arc::clone(&connection)).with_keep_alive(true);
+        let mut api = crate::SnowflakeApi::new(connection, session, "TEST".to_string());
+        api.exec("SELECT 1").await.unwrap();
+        api.close_session().await.unwrap();
+        api.exec("SELECT 1").await.unwrap();
+
+        let keep_alive: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.url.path() == "/session/v1/login-request")
+            .map(|r| {
+                r.body_json::<serde_json::Value>().unwrap()["data"]["SESSION_PARAMETERS"]
+                    ["CLIENT_SESSION_KEEP_ALIVE"]
+                    .clone()
+            })
+            .collect();
+        assert_eq!(keep_alive, vec![json!(true), serde_json::Value::Null]);
+    }
+}
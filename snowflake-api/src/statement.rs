@@ -0,0 +1,186 @@
+use crate::{QueryResult, SnowflakeApiError};
+
+const KIND_MASK: i64 = 0xF000;
+const DML_KIND_MASK: i64 = 0xFF00;
+
+/// Kind of the executed statement, decoded from the `statementTypeId` of the response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementType {
+    /// `SELECT`, `EXPLAIN` and other queries returning a result set
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Merge,
+    MultiTableInsert,
+    /// `COPY INTO <table>`
+    Copy,
+    /// `COPY INTO <location>`
+    Unload,
+    /// Other DML statements
+    Dml,
+    /// Session control, eg `ALTER SESSION`, `USE`, `SHOW` and `DESCRIBE`
+    Scl,
+    /// Transaction control, eg `BEGIN` and `COMMIT`
+    Tcl,
+    Ddl,
+    /// `PUT`, `GET`, `LIST` and `REMOVE`
+    Stage,
+    MultiStatement,
+    Unknown(i64),
+}
+
+impl From<i64> for StatementType {
+    fn from(id: i64) -> Self {
+        match id & KIND_MASK {
+            0x1000 => Self::Select,
+            0x3000 => match id & DML_KIND_MASK {
+                0x3100 => Self::Insert,
+                0x3200 => Self::Update,
+                0x3300 => Self::Delete,
+                0x3400 => Self::Merge,
+                0x3500 => Self::MultiTableInsert,
+                0x3600 => Self::Copy,
+                0x3700 => Self::Unload,
+                _ => Self::Dml,
+            },
+            0x4000 => Self::Scl,
+            0x5000 => Self::Tcl,
+            0x6000 => Self::Ddl,
+            0x7000 => Self::Stage,
+            0xA000 => Self::MultiStatement,
+            _ => Self::Unknown(id),
+        }
+    }
+}
+
+impl StatementType {
+    /// Whether the result is a single row of affected row counts, see [`DmlResult`]
+    pub fn is_dml(&self) -> bool {
+        matches!(
+            self,
+            Self::Insert
+                | Self::Update
+                | Self::Delete
+                | Self::Merge
+                | Self::MultiTableInsert
+                | Self::Unload
+                | Self::Dml
+        )
+    }
+}
+
+/// Row counts reported by the DML statement
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DmlResult {
+    /// For multi-table insert it's the total across all tables
+    pub rows_inserted: u64,
+    pub rows_updated: u64,
+    pub rows_deleted: u64,
+    /// Target rows updated more than once, when they are joined with several source rows
+    pub rows_multi_joined: u64,
+    pub rows_unloaded: u64,
+}
+
+impl DmlResult {
+    pub fn rows_affected(&self) -> u64 {
+        self.rows_inserted + self.rows_updated + self.rows_deleted
+    }
+}
+
+impl QueryResult {
+    pub fn statement_type(&self) -> Option<StatementType> {
+        let id = match self {
+            QueryResult::Arrow(result) => result.statement_type_id,
+            QueryResult::Json(result) => result.statement_type_id,
+            QueryResult::Empty(result) => result.statement_type_id,
+        };
+        id.map(Into::into)
+    }
+
+    /// Row counts of the DML statement, decoded from its `number of rows ...` result columns.
+    /// `None` if the statement isn't DML.
+    pub fn dml_result(&self) -> Result<Option<DmlResult>, SnowflakeApiError> {
+        if !self.statement_type().is_some_and(|t| t.is_dml()) {
+            return Ok(None);
+        }
+
+        let mut dml = DmlResult::default();
+        let rows = self.rows()?;
+        let Some(row) = rows.first() else {
+            return Ok(Some(dml));
+        };
+        for (i, column) in row.columns().iter().enumerate() {
+            let column = column.to_ascii_lowercase();
+            let count = if column.starts_with("number of rows inserted") {
+                &mut dml.rows_inserted
+            } else if column.starts_with("number of rows updated") {
+                &mut dml.rows_updated
+            } else if column.starts_with("number of rows deleted") {
+                &mut dml.rows_deleted
+            } else if column.starts_with("number of multi-joined rows updated") {
+                &mut dml.rows_multi_joined
+            } else if column == "rows_unloaded" || column == "number of rows unloaded" {
+                &mut dml.rows_unloaded
+            } else {
+                continue;
+            };
+            *count += row.get::<Option<u64>>(i)?.unwrap_or(0);
+        }
+
+        Ok(Some(dml))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonResult;
+
+    #[test]
+    fn test_statement_type_from_id() {
+        assert_eq!(StatementType::from(0x1000), StatementType::Select);
+        assert_eq!(StatementType::from(0x3100), StatementType::Insert);
+        assert_eq!(StatementType::from(0x3400), StatementType::Merge);
+        assert_eq!(StatementType::from(0x3800), StatementType::Dml);
+        assert_eq!(StatementType::from(0x4100), StatementType::Scl);
+        assert_eq!(StatementType::from(0x6000), StatementType::Ddl);
+        assert_eq!(StatementType::from(0xA000), StatementType::MultiStatement);
+        assert_eq!(StatementType::from(0x9000), StatementType::Unknown(0x9000));
+        assert!(StatementType::from(0x3300).is_dml());
+        assert!(!StatementType::from(0x3600).is_dml());
+    }
+
+    #[test]
+    fn test_dml_result() {
+        let result = JsonResult {
+            statement_type_id: Some(0x3400),
+            ..JsonResult::from_rowtype(
+                serde_json::json!([
+                    {"name": "number of rows inserted", "type": "fixed", "scale": 0, "precision": 19, "nullable": false},
+                    {"name": "number of rows updated", "type": "fixed", "scale": 0, "precision": 19, "nullable": false},
+                    {"name": "number of rows deleted", "type": "fixed", "scale": 0, "precision": 19, "nullable": false}
+                ]),
+                serde_json::json!([["3", "2", "1"]]),
+            )
+        };
+
+        let dml = QueryResult::Json(result).dml_result().unwrap().unwrap();
+        assert_eq!(
+            dml,
+            DmlResult {
+                rows_inserted: 3,
+                rows_updated: 2,
+                rows_deleted: 1,
+                ..DmlResult::default()
+            }
+        );
+        assert_eq!(dml.rows_affected(), 6);
+
+        let result = JsonResult {
+            statement_type_id: Some(0x1000),
+            ..JsonResult::from_rowtype(serde_json::json!([]), serde_json::json!([]))
+        };
+        assert_eq!(QueryResult::Json(result).dml_result().unwrap(), None);
+    }
+}
@@ -0,0 +1,273 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::connection::QueryType;
+use crate::responses::{MonitoringResponse, QueryMonitoringData, QueryState};
+use crate::{SnowflakeApi, SnowflakeApiError};
+
+/// Called with the query status every time the running query is polled
+pub type ProgressCallback = Arc<dyn Fn(&QueryStatus) + Send + Sync>;
+
+/// How often the result of the long-running query is polled and for how long
+#[derive(Debug, Clone)]
+pub struct PollPolicy {
+    /// Delay before the first poll
+    pub initial_delay: Duration,
+    /// Upper bound for the delay between polls
+    pub max_delay: Duration,
+    /// Delay is multiplied by this after every poll
+    pub multiplier: f64,
+    /// Overall time to wait for the query to finish, `query_aborts_after_secs` of the response if not set
+    pub deadline: Option<Duration>,
+}
+
+impl Default for PollPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+            deadline: None,
+        }
+    }
+}
+
+impl PollPolicy {
+    pub(crate) fn next_delay(&self, delay: Duration) -> Duration {
+        // `mul_f64` panics on overflow, eg with infinite multiplier
+        Duration::try_from_secs_f64(delay.as_secs_f64() * self.multiplier.max(1.0))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Deadline for the query which server aborts after `query_aborts_after_secs`
+    pub(crate) fn deadline(&self, query_aborts_after_secs: i64) -> Option<Duration> {
+        self.deadline.or_else(|| {
+            u64::try_from(query_aborts_after_secs)
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+        })
+    }
+}
+
+/// Query status as reported by the monitoring endpoint
+#[derive(Debug, Clone)]
+pub struct QueryStatus {
+    pub query_id: String,
+    pub state: QueryState,
+    /// Only set if query has failed
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    /// Only set if query has finished
+    pub end_time: Option<DateTime<Utc>>,
+    /// Time query took to execute so far
+    pub total_duration: Option<Duration>,
+}
+
+impl QueryStatus {
+    /// Status of the query not (yet) known to the monitoring endpoint
+    fn no_data(query_id: &str) -> Self {
+        Self {
+            query_id: query_id.to_string(),
+            state: QueryState::NoData,
+            error_code: None,
+            error_message: None,
+            start_time: None,
+            end_time: None,
+            total_duration: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.is_running()
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.state.is_error()
+    }
+}
+
+impl From<QueryMonitoringData> for QueryStatus {
+    fn from(value: QueryMonitoringData) -> Self {
+        Self {
+            query_id: value.id,
+            state: value.status,
+            error_code: value.error_code,
+            error_message: value.error_message,
+            start_time: value.start_time.and_then(DateTime::from_timestamp_millis),
+            // 0 until the query is finished
+            end_time: value
+                .end_time
+                .filter(|t| *t > 0)
+                .and_then(DateTime::from_timestamp_millis),
+            total_duration: value
+                .total_duration
+                .and_then(|d| u64::try_from(d).ok())
+                .map(Duration::from_millis),
+        }
+    }
+}
+
+impl SnowflakeApi {
+    /// Current status of the query, returns right away.
+    /// Query doesn't have to be issued by this session.
+    pub async fn query_status(&self, query_id: &str) -> Result<QueryStatus, SnowflakeApiError> {
+        let resp = self
+            .poll::<MonitoringResponse>(
+                &format!("/monitoring/queries/{query_id}"),
+                QueryType::JsonQuery,
+            )
+            .await?;
+
+        Ok(resp
+            .data
+            .queries
+            .into_iter()
+            .next()
+            .map_or_else(|| QueryStatus::no_data(query_id), Into::into))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::test_utils::{
+        api, monitoring_response, mount_login, running_response, select_one_response,
+    };
+
+    #[test]
+    fn test_status_from_monitoring_response() {
+        let resp: MonitoringResponse = serde_json::from_value(serde_json::json!({
+            "code": null,
+            "message": null,
+            "success": true,
+            "data": {
+                "queries": [{
+                    "id": "01b2c3d4-0000-0001-0000-000000000001",
+                    "status": "FAILED_WITH_ERROR",
+                    "errorCode": "000604",
+                    "errorMessage": "SQL execution canceled",
+                    "startTime": 1_700_000_000_000_i64,
+                    "endTime": 1_700_000_001_500_i64,
+                    "totalDuration": 1500,
+                    "sqlText": "select 1"
+                }]
+            }
+        }))
+        .unwrap();
+
+        let status: QueryStatus = resp.data.queries.into_iter().next().unwrap().into();
+        assert_eq!(status.state, QueryState::FailedWithError);
+        assert!(status.is_error());
+        assert_eq!(status.error_code.as_deref(), Some("000604"));
+        assert_eq!(
+            status.start_time,
+            DateTime::from_timestamp_millis(1_700_000_000_000)
+        );
+        assert_eq!(status.total_duration, Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_poll_policy_backoff() {
+        let policy = PollPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            multiplier: 2.0,
+            deadline: None,
+        };
+        let delay = policy.next_delay(policy.initial_delay);
+        assert_eq!(delay, Duration::from_millis(200));
+        assert_eq!(policy.next_delay(delay), Duration::from_millis(300));
+
+        let policy = PollPolicy {
+            multiplier: f64::INFINITY,
+            ..policy
+        };
+        assert_eq!(policy.next_delay(delay), Duration::from_millis(300));
+
+        assert_eq!(policy.deadline(90), Some(Duration::from_secs(90)));
+        assert_eq!(policy.deadline(0), None);
+    }
+
+    #[tokio::test]
+    async fn test_monitoring_failure_doesnt_fail_query() {
+        let query_id = "01b2c3d4-0000-0001-0000-000000000001";
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .respond_with(running_response(query_id))
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("/monitoring/queries/{query_id}")))
+            // not transient, so it isn't retried
+            .respond_with(ResponseTemplate::new(404))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("/monitoring/queries/{query_id}")))
+            .respond_with(monitoring_response(query_id, "RUNNING"))
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("/queries/{query_id}/result")))
+            .respond_with(running_response(query_id))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("/queries/{query_id}/result")))
+            .respond_with(select_one_response(query_id))
+            .mount(&server)
+            .await;
+
+        let states = Arc::new(Mutex::new(vec![]));
+        let reported = Arc::clone(&states);
+        let mut api = api(&server);
+        api.poll_policy.initial_delay = Duration::from_millis(1);
+        api.progress_callback = Some(Arc::new(move |status: &QueryStatus| {
+            reported.lock().unwrap().push(status.state);
+        }));
+
+        let result = api.exec("SELECT 1").await.unwrap().data;
+        assert_eq!(result.rows().unwrap()[0].get::<i64>(0).unwrap(), 1);
+        // first status request has failed and is skipped
+        assert_eq!(*states.lock().unwrap(), vec![QueryState::Running]);
+    }
+
+    #[tokio::test]
+    async fn test_query_timeout_after_deadline() {
+        let query_id = "01b2c3d4-0000-0001-0000-000000000001";
+        let server = MockServer::start().await;
+        mount_login(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .respond_with(running_response(query_id))
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("/queries/{query_id}/result")))
+            .respond_with(running_response(query_id))
+            .mount(&server)
+            .await;
+
+        let mut api = api(&server);
+        api.poll_policy = PollPolicy {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(10),
+            multiplier: 1.0,
+            deadline: Some(Duration::from_millis(100)),
+        };
+
+        let err = api.exec("SELECT 1").await.unwrap_err();
+        assert!(matches!(
+            err,
+            SnowflakeApiError::QueryTimeout { query_id: ref id } if id == query_id
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+//! Shared fixtures of the tests against the mocked Snowflake REST API
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::connection::Connection;
+use crate::session::Session;
+use crate::SnowflakeApi;
+
+pub(crate) fn connection(server: &MockServer) -> Arc<Connection> {
+    Arc::new(Connection::new().unwrap().with_base_url(&server.uri()))
+}
+
+/// Session logging in with password, see [`login_response`]
+pub(crate) fn session(connection: Arc<Connection>) -> Session {
+    Session::password_auth(
+        connection, "test", None, None, None, "user", None, "password",
+    )
+}
+
+pub(crate) fn api(server: &MockServer) -> SnowflakeApi {
+    let connection = connection(server);
+    let session = session(Arc::clone(&connection));
+    SnowflakeApi::new(connection, session, "TEST".to_string())
+}
+
+/// Password login succeeding with the `session` token
+pub(crate) async fn mount_login(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/session/v1/login-request"))
+        .respond_with(login_response("session"))
+        .mount(server)
+        .await;
+}
+
+/// Successful login, session token is `token` and master token is `master`
+pub(crate) fn login_response(token: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "code": null,
+        "message": null,
+        "success": true,
+        "data": {
+            "sessionId": 1,
+            "token": token,
+            "masterToken": "master",
+            "serverVersion": "8.0.0",
+            "sessionInfo": {"roleName": "PUBLIC"},
+            "masterValidityInSeconds": 14400,
+            "validityInSeconds": 3600
+        }
+    }))
+}
+
+/// Successful response with the given `data`
+pub(crate) fn success_response(data: &Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "code": null,
+        "message": null,
+        "success": true,
+        "data": data
+    }))
+}
+
+/// `data` of the finished query with the JSON result set
+pub(crate) fn json_result_data(query_id: &str, rowtype: &Value, rowset: &Value) -> Value {
+    let rows = rowset.as_array().map_or(0, Vec::len);
+    json!({
+        "rowtype": rowtype,
+        "rowset": rowset,
+        "total": rows,
+        "returned": rows,
+        "queryId": query_id,
+        "statementTypeId": 4096,
+        "sendResultTime": 0,
+        "queryContext": {"entries": []}
+    })
+}
+
+/// Finished `SELECT 1 AS ONE`
+pub(crate) fn select_one_response(query_id: &str) -> ResponseTemplate {
+    success_response(&json_result_data(
+        query_id,
+        &json!([{"name": "ONE", "type": "fixed", "precision": 1, "scale": 0, "nullable": false}]),
+        &json!([["1"]]),
+    ))
+}
+
+/// Query is still running, result is to be polled at `/queries/{query_id}/result`
+pub(crate) fn running_response(query_id: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "code": "333334",
+        "message": "Asynchronous execution in progress. Use provided query id to perform query monitoring and management.",
+        "success": true,
+        "data": {
+            "queryId": query_id,
+            "getResultUrl": format!("/queries/{query_id}/result"),
+            "queryAbortsAfterSecs": 300,
+            "progressDesc": null
+        }
+    }))
+}
+
+/// Response of the monitoring endpoint with the query in the given state, eg `RUNNING`
+pub(crate) fn monitoring_response(query_id: &str, status: &str) -> ResponseTemplate {
+    success_response(&json!({
+        "queries": [{
+            "id": query_id,
+            "status": status,
+            "startTime": 1_700_000_000_000_i64,
+            "endTime": 0,
+            "totalDuration": 1500,
+            "sqlText": "SELECT 1"
+        }]
+    }))
+}
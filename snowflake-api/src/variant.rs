@@ -0,0 +1,300 @@
+//! Semi-structured `VARIANT`, `OBJECT`, `ARRAY` and `MAP` columns.
+//! Snowflake sends them as JSON text, Arrow fields of such columns are tagged with the canonical
+//! `arrow.json` extension type and row accessors parse them into `serde_json::Value`.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use arrow::datatypes::{DataType, Field};
+use serde_json::Value;
+
+use crate::{CellValue, FromSnowflake, Row, SnowflakeApiError};
+
+pub(crate) const EXTENSION_NAME_KEY: &str = "ARROW:extension:name";
+pub(crate) const JSON_EXTENSION_NAME: &str = "arrow.json";
+
+/// Whether the column holds JSON text, by Snowflake logical type or Arrow extension type
+pub(crate) fn is_json_field(field: &Field) -> bool {
+    let metadata = field.metadata();
+    // Arrow results have `MAP` as the Arrow map
+    matches!(field.data_type(), DataType::Utf8 | DataType::LargeUtf8)
+        && metadata
+            .get("logicalType")
+            .is_some_and(|t| is_semi_structured(t))
+        || metadata
+            .get(EXTENSION_NAME_KEY)
+            .is_some_and(|name| name == JSON_EXTENSION_NAME)
+}
+
+pub(crate) fn is_semi_structured(logical_type: &str) -> bool {
+    matches!(logical_type, "VARIANT" | "OBJECT" | "ARRAY" | "MAP")
+}
+
+/// Tag the field metadata with the `arrow.json` extension type
+pub(crate) fn add_json_extension(metadata: &mut HashMap<String, String>) {
+    metadata.insert(
+        EXTENSION_NAME_KEY.to_string(),
+        JSON_EXTENSION_NAME.to_string(),
+    );
+}
+
+/// Path into the semi-structured column in Snowflake notation, eg `payload:customer.address[0]`,
+/// `payload:"Mixed Case"` or `tags[1]`.
+/// Keys are case-sensitive, same as in Snowflake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantPath {
+    column: String,
+    segments: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl VariantPath {
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Element of the column value at the path, `None` if there is no such element
+    pub fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                PathSegment::Key(key) => value.get(key),
+                PathSegment::Index(index) => value.get(index),
+            })
+    }
+}
+
+impl FromStr for VariantPath {
+    type Err = SnowflakeApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |message: &str| SnowflakeApiError::InvalidVariantPath(format!("`{s}`: {message}"));
+
+        let column_end = s.find([':', '[']).unwrap_or(s.len());
+        let column = s[..column_end].trim();
+        if column.is_empty() {
+            return Err(invalid("column name is missing"));
+        }
+
+        let mut chars = s[column_end..].chars().peekable();
+        let mut segments = vec![];
+        if chars.next_if_eq(&':').is_some() {
+            segments.push(PathSegment::Key(parse_key(&mut chars).map_err(invalid)?));
+        }
+        while let Some(c) = chars.next() {
+            let segment = match c {
+                '.' => PathSegment::Key(parse_key(&mut chars).map_err(invalid)?),
+                '[' => {
+                    let segment = parse_bracket(&mut chars).map_err(invalid)?;
+                    if chars.next() != Some(']') {
+                        return Err(invalid("expected `]`"));
+                    }
+                    segment
+                }
+                c => return Err(invalid(&format!("unexpected `{c}`"))),
+            };
+            segments.push(segment);
+        }
+
+        Ok(Self {
+            column: column.to_string(),
+            segments,
+        })
+    }
+}
+
+fn parse_key(chars: &mut Peekable<Chars>) -> Result<String, &'static str> {
+    if chars.next_if_eq(&'"').is_some() {
+        return parse_quoted(chars, '"');
+    }
+
+    let mut key = String::new();
+    while let Some(c) = chars.next_if(|c| !matches!(c, '.' | '[' | ']' | ':' | '"')) {
+        key.push(c);
+    }
+    if key.is_empty() {
+        Err("key is missing")
+    } else {
+        Ok(key)
+    }
+}
+
+fn parse_bracket(chars: &mut Peekable<Chars>) -> Result<PathSegment, &'static str> {
+    if let Some(quote) = chars.next_if(|c| matches!(c, '\'' | '"')) {
+        return parse_quoted(chars, quote).map(PathSegment::Key);
+    }
+
+    let mut index = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        index.push(c);
+    }
+    index
+        .parse()
+        .map(PathSegment::Index)
+        .map_err(|_| "expected array index or quoted key")
+}
+
+fn parse_quoted(chars: &mut Peekable<Chars>, quote: char) -> Result<String, &'static str> {
+    let mut key = String::new();
+    loop {
+        match chars.next() {
+            // doubled quote is the escaped one
+            Some(c) if c == quote && chars.next_if_eq(&quote).is_none() => return Ok(key),
+            Some(c) => key.push(c),
+            None => return Err("unterminated quoted key"),
+        }
+    }
+}
+
+impl Display for VariantPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.column)?;
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 && is_plain_key(key) => write!(f, ":{key}")?,
+                PathSegment::Key(key) => write_key(f, key)?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+fn write_key(f: &mut impl std::fmt::Write, key: &str) -> std::fmt::Result {
+    if is_plain_key(key) {
+        write!(f, ".{key}")
+    } else {
+        write!(f, "['{}']", key.replace('\'', "''"))
+    }
+}
+
+/// All leaf elements of the value with their paths relative to the value, like
+/// `FLATTEN(RECURSIVE => TRUE)` does, eg `[("a.b[0]", 1), ("c", "x")]`.
+/// Empty objects and arrays are leaves too, path of the scalar value is empty.
+pub fn flatten(value: &Value) -> Vec<(String, &Value)> {
+    let mut leaves = vec![];
+    flatten_into(value, String::new(), &mut leaves);
+    leaves
+}
+
+fn flatten_into<'a>(value: &'a Value, path: String, leaves: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                let child = if path.is_empty() && is_plain_key(key) {
+                    key.clone()
+                } else {
+                    let mut child = path.clone();
+                    // writing to String never fails
+                    let _ = write_key(&mut child, key);
+                    child
+                };
+                flatten_into(value, child, leaves);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (index, value) in array.iter().enumerate() {
+                flatten_into(value, format!("{path}[{index}]"), leaves);
+            }
+        }
+        value => leaves.push((path, value)),
+    }
+}
+
+impl Row {
+    /// Element of the semi-structured column at the path in Snowflake notation,
+    /// eg `row.get_path("PAYLOAD:customer.address[0]")`.
+    /// Missing elements are `Value::Null`, same as in Snowflake.
+    pub fn get_path(&self, path: &str) -> Result<Value, SnowflakeApiError> {
+        let path: VariantPath = path.parse()?;
+        let value = match self.get::<CellValue>(path.column())? {
+            // columns without type information are kept as text
+            CellValue::Text(text) => serde_json::from_str(&text).unwrap_or(Value::String(text)),
+            cell => Value::from_cell(&cell).unwrap_or_default(),
+        };
+
+        Ok(path.get(&value).cloned().unwrap_or(Value::Null))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_variant_path() {
+        let path: VariantPath = r#"PAYLOAD:customer.addresses[1]["zip code"]"#.parse().unwrap();
+        assert_eq!(path.column(), "PAYLOAD");
+        assert_eq!(
+            path.segments(),
+            &[
+                PathSegment::Key("customer".to_string()),
+                PathSegment::Key("addresses".to_string()),
+                PathSegment::Index(1),
+                PathSegment::Key("zip code".to_string()),
+            ]
+        );
+        assert_eq!(
+            path.to_string(),
+            "PAYLOAD:customer.addresses[1]['zip code']"
+        );
+
+        let value = json!({"customer": {"addresses": [{}, {"zip code": "10115"}]}});
+        assert_eq!(path.get(&value), Some(&json!("10115")));
+        assert_eq!(
+            "PAYLOAD:missing"
+                .parse::<VariantPath>()
+                .unwrap()
+                .get(&value),
+            None
+        );
+        assert_eq!(
+            "TAGS[0]".parse::<VariantPath>().unwrap().get(&json!(["a"])),
+            Some(&json!("a"))
+        );
+
+        for invalid in [":a", "col:", "col:a[x]", "col:a[1", "col:\"a"] {
+            assert!(invalid.parse::<VariantPath>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_flatten() {
+        let value = json!({"a": {"b": [1, 2]}, "c d": "x", "e": {}});
+        let leaves: Vec<_> = flatten(&value)
+            .into_iter()
+            .map(|(path, value)| (path, value.clone()))
+            .collect();
+        assert_eq!(
+            leaves,
+            vec![
+                ("a.b[0]".to_string(), json!(1)),
+                ("a.b[1]".to_string(), json!(2)),
+                ("['c d']".to_string(), json!("x")),
+                ("e".to_string(), json!({})),
+            ]
+        );
+        assert_eq!(flatten(&json!(1)), vec![(String::new(), &json!(1))]);
+    }
+}